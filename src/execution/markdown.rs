@@ -1,14 +1,44 @@
+use crate::templates::MarkdownDocument;
 use color_eyre::eyre::Context;
+use std::collections::BTreeMap;
 use tera::Tera;
 
 pub fn render_template_with_md(
     template: &str,
-    frontmatter: &impl serde::Serialize,
-    content: &str,
+    document: &MarkdownDocument,
+    data: Option<&[(String, String)]>,
     escape: bool,
 ) -> color_eyre::Result<String> {
-    let mut ctx = tera::Context::from_serialize(frontmatter)?;
-    ctx.insert("content", content);
+    let mut ctx = tera::Context::new();
+    if let Some(pairs) = data {
+        for (key, value) in pairs {
+            ctx.insert(key.clone(), value);
+        }
+    }
+    ctx.extend(tera::Context::from_serialize(&document.frontmatter)?);
+    ctx.insert("content", &document.content);
+    ctx.insert("summary", &document.summary);
+    ctx.insert("summary_text", &document.summary_text);
+    ctx.insert("raw_content", &document.raw_content);
+    ctx.insert("text_content", &document.text_content);
+    ctx.insert("word_count", &document.word_count);
+    ctx.insert("char_count", &document.char_count);
+    ctx.insert("reading_time", &document.reading_time);
+    ctx.insert("toc", &document.toc);
+    ctx.insert("footnotes", &document.footnotes);
+    ctx.insert("footnotes_html", &document.footnotes_html);
+    ctx.insert("bibliography", &document.bibliography);
+    ctx.insert("figures", &document.figures);
+    // Tera maps don't keep their order, so `section_list` is the one in document order.
+    let sections: BTreeMap<_, _> = document
+        .sections
+        .iter()
+        .map(|section| (&section.id, section))
+        .collect();
+    ctx.insert("sections", &sections);
+    ctx.insert("section_list", &document.sections);
+    ctx.insert("slides", &document.slides);
+    ctx.insert("ast", &document.ast);
 
     Tera::one_off(template, &ctx, escape).wrap_err("Failed to render template")
 }
@@ -16,6 +46,8 @@ pub fn render_template_with_md(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::templates::TocEntry;
+    use markdown::mdast::{Node, Root};
     use rstest::*;
 
     #[fixture]
@@ -47,6 +79,32 @@ mod tests {
         empty_table
     }
 
+    fn document(frontmatter: toml::Table, content: &str) -> MarkdownDocument {
+        MarkdownDocument {
+            frontmatter,
+            content: content.to_string(),
+            summary: String::new(),
+            summary_text: String::new(),
+            raw_content: String::new(),
+            text_content: String::new(),
+            word_count: 0,
+            char_count: 0,
+            reading_time: 0,
+            toc: Vec::new(),
+            footnotes: Vec::new(),
+            footnotes_html: String::new(),
+            bibliography: String::new(),
+            figures: Vec::new(),
+            sections: Vec::new(),
+            slides: Vec::new(),
+            ast: Node::Root(Root {
+                children: Vec::new(),
+                position: None,
+            }),
+            warnings: Vec::new(),
+        }
+    }
+
     #[rstest]
     fn render_template_works_with_valid_inputs(
         template: &str,
@@ -55,8 +113,8 @@ mod tests {
     ) {
         let result = render_template_with_md(
             template,
-            &title_frontmatter,
-            html_content,
+            &document(title_frontmatter, html_content),
+            None,
             false,
         );
         assert!(result.is_ok());
@@ -74,7 +132,12 @@ mod tests {
         template: &str,
         empty_table: toml::Table,
     ) {
-        let result = render_template_with_md(template, &empty_table, "", false);
+        let result = render_template_with_md(
+            template,
+            &document(empty_table, ""),
+            None,
+            false,
+        );
         assert!(result.is_err());
     }
 
@@ -86,10 +149,87 @@ mod tests {
     ) {
         let result = render_template_with_md(
             template_2,
-            &title_frontmatter,
-            html_content,
+            &document(title_frontmatter, html_content),
+            None,
             false,
         );
         assert!(result.is_err(), "{result:?}");
     }
+
+    #[rstest]
+    fn render_template_exposes_the_toc(title_frontmatter: toml::Table) {
+        let document = MarkdownDocument {
+            toc: vec![TocEntry {
+                level: 1,
+                text: "Intro".to_string(),
+                id: "intro".to_string(),
+                children: Vec::new(),
+            }],
+            ..document(title_frontmatter, "")
+        };
+
+        let result = render_template_with_md(
+            "{% for entry in toc %}<a href=\"#{{ entry.id }}\">{{ entry.text }}</a>{% endfor %}",
+            &document,
+            None,
+            false,
+        );
+
+        let output = result.expect("Failed to render template");
+        assert_eq!(output, "<a href=\"#intro\">Intro</a>");
+    }
+
+    #[rstest]
+    fn render_template_exposes_the_markdown_document(
+        title_frontmatter: toml::Table,
+    ) {
+        let input = "+++\n+++\n\n# A *heading*";
+        let ast = markdown::to_mdast(input, &markdown::ParseOptions::gfm())
+            .expect("This should not fail");
+        let document = MarkdownDocument {
+            summary: "<p>Summary</p>".to_string(),
+            summary_text: "Summary".to_string(),
+            raw_content: "# A *heading*".to_string(),
+            text_content: "A heading".to_string(),
+            word_count: 2,
+            reading_time: 1,
+            ast,
+            ..document(title_frontmatter, "")
+        };
+
+        let result = render_template_with_md(
+            "{{ summary }}|{{ summary_text }}|{{ raw_content }}|{{ text_content }}|\
+            {{ word_count }} words, {{ reading_time }} min read|{{ ast.type }}",
+            &document,
+            None,
+            false,
+        );
+
+        let output = result.expect("Failed to render template");
+        assert_eq!(
+            output,
+            "<p>Summary</p>|Summary|# A *heading*|A heading|\
+            2 words, 1 min read|root"
+        );
+    }
+
+    #[rstest]
+    fn render_template_exposes_data_under_the_frontmatter(
+        title_frontmatter: toml::Table,
+    ) {
+        let data = [
+            ("title".to_string(), "Default".to_string()),
+            ("site".to_string(), "Blog".to_string()),
+        ];
+
+        let result = render_template_with_md(
+            "{{ title }} - {{ site }}",
+            &document(title_frontmatter, ""),
+            Some(&data),
+            false,
+        );
+
+        let output = result.expect("Failed to render template");
+        assert_eq!(output, "Test - Blog");
+    }
 }
@@ -4,8 +4,17 @@ mod markdown;
 use self::{
     arguments::render_template_with_args, markdown::render_template_with_md,
 };
-use crate::{interface::Command, templates::process_md_file};
-use camino::Utf8Path;
+use crate::{
+    interface::{Command, Construct, Flavor, HighlightStyle, MarkdownArgs},
+    templates::{
+        Callouts, Citations, CodeHighlighting, CrossReferences,
+        DEFAULT_HIGHLIGHT_THEME, Figures, HtmlHooks, HtmlSanitizer, Includes,
+        MarkdownConstruct, MarkdownFlavor, MarkdownOptions, Shortcodes, Vault,
+        highlight_stylesheet, highlight_themes, process_md_file,
+    },
+};
+use camino::{Utf8Path, Utf8PathBuf};
+use color_eyre::eyre::bail;
 
 #[allow(clippy::missing_errors_doc)]
 pub fn execute_application(command: Command) -> color_eyre::Result<()> {
@@ -20,11 +29,183 @@ pub fn execute_application(command: Command) -> color_eyre::Result<()> {
             &args.template,
             &args.input,
             args.output.as_deref(),
-            args.inline_html,
+            args.data.as_deref(),
+            &markdown_options(&args)?,
+        ),
+        Command::HighlightCss(args) => create_highlight_stylesheet(
+            args.theme.as_deref(),
+            args.output.as_deref(),
         ),
     }
 }
 
+fn markdown_options(
+    args: &MarkdownArgs,
+) -> color_eyre::Result<MarkdownOptions> {
+    let vault = args
+        .vault
+        .as_deref()
+        .map(|root| Vault::load(root, &args.input))
+        .transpose()?;
+
+    let mut hooks = HtmlHooks {
+        table_wrapper: args.wrap_tables.clone(),
+        ..HtmlHooks::default()
+    };
+    for (element, name, value) in &args.attribute {
+        hooks.add_attribute(element, name, value)?;
+    }
+
+    if args.math && args.disable.contains(&Construct::Math) {
+        bail!("`--disable math` conflicts with `--math`");
+    }
+
+    Ok(MarkdownOptions {
+        includes: args.includes.then(|| Includes {
+            current: args.input.clone(),
+            root: input_root(args),
+        }),
+        render_body: args.render_body,
+        variables: args.data.iter().flatten().cloned().collect(),
+        shortcodes: args
+            .shortcodes
+            .as_deref()
+            .map(Shortcodes::load)
+            .transpose()?,
+        flavor: match args.flavor {
+            Flavor::Gfm => MarkdownFlavor::Gfm,
+            Flavor::CommonMark => MarkdownFlavor::CommonMark,
+        },
+        constructs: args
+            .enable
+            .iter()
+            .map(|construct| (markdown_construct(*construct), true))
+            .chain(
+                args.disable
+                    .iter()
+                    .map(|construct| (markdown_construct(*construct), false)),
+            )
+            .collect(),
+        inline_html: args.inline_html,
+        sanitizer: args.sanitize.then(|| html_sanitizer(args)),
+        heading_offset: args.shift_headings,
+        heading_anchors: args.anchors,
+        code_highlighting: args
+            .highlight
+            .map(|style| code_highlighting(style, args.theme.as_deref()))
+            .transpose()?,
+        math: args.math || args.enable.contains(&Construct::Math),
+        diagrams: args.diagrams,
+        emoji: args.emoji,
+        smart_punctuation: args.smart_punctuation,
+        summary_words: args.summary_words,
+        citations: (args.citations || args.bibliography.is_some()).then(|| {
+            Citations {
+                bibliography: args.bibliography.clone(),
+                directory: args
+                    .input
+                    .parent()
+                    .map(Utf8Path::to_path_buf)
+                    .unwrap_or_default(),
+                root: input_root(args),
+                style: args.csl.clone(),
+            }
+        }),
+        separate_footnotes: args.separate_footnotes,
+        figures: (args.figures || args.number_figures || args.cross_references)
+            .then(|| Figures {
+                numbered: args.number_figures,
+                label: args.figure_label.clone(),
+            }),
+        cross_references: args.cross_references.then(|| CrossReferences {
+            section_label: args.section_label.clone(),
+            table_label: args.table_label.clone(),
+        }),
+        sections: args.sections,
+        slides: args.slides,
+        callouts: args.callouts.then(|| Callouts {
+            class: args.callout_class.clone(),
+            titles: args
+                .callout_title
+                .iter()
+                .map(|(kind, title)| (kind.to_lowercase(), title.clone()))
+                .collect(),
+        }),
+        html_links: args.html_links,
+        base_url: args.base_url.clone(),
+        hooks,
+        vault,
+    })
+}
+
+/// Returns the directory that the files referenced by the input have to be inside of.
+fn input_root(args: &MarkdownArgs) -> Utf8PathBuf {
+    args.include_root.clone().unwrap_or_else(|| {
+        args.input
+            .parent()
+            .filter(|parent| !parent.as_str().is_empty())
+            .map_or_else(|| Utf8PathBuf::from("."), Utf8Path::to_path_buf)
+    })
+}
+
+fn html_sanitizer(args: &MarkdownArgs) -> HtmlSanitizer {
+    let mut sanitizer = HtmlSanitizer::default();
+    sanitizer
+        .tags
+        .extend(args.allow_tag.iter().map(|tag| tag.to_lowercase()));
+    for attribute in &args.allow_attribute {
+        sanitizer.allow_attribute(attribute);
+    }
+    sanitizer
+        .url_schemes
+        .extend(args.allow_scheme.iter().map(|scheme| scheme.to_lowercase()));
+
+    sanitizer
+}
+
+fn code_highlighting(
+    style: HighlightStyle,
+    theme: Option<&str>,
+) -> color_eyre::Result<CodeHighlighting> {
+    match style {
+        HighlightStyle::Inline => Ok(CodeHighlighting::Inline {
+            theme: highlight_theme(theme)?.to_string(),
+        }),
+        HighlightStyle::Classes if theme.is_some() => {
+            bail!("`--theme` only applies to `--highlight inline`")
+        }
+        HighlightStyle::Classes => Ok(CodeHighlighting::Classes),
+    }
+}
+
+/// Returns `theme`, or the default theme if it's `None`.
+fn highlight_theme(theme: Option<&str>) -> color_eyre::Result<&str> {
+    let theme = theme.unwrap_or(DEFAULT_HIGHLIGHT_THEME);
+    if !highlight_themes().any(|name| name == theme) {
+        let themes: Vec<_> = highlight_themes().collect();
+        bail!(
+            "Unknown highlighting theme `{theme}`. Available themes: {}",
+            themes.join(", ")
+        );
+    }
+
+    Ok(theme)
+}
+
+const fn markdown_construct(construct: Construct) -> MarkdownConstruct {
+    match construct {
+        Construct::Autolinks => MarkdownConstruct::Autolinks,
+        Construct::AutolinkLiterals => MarkdownConstruct::AutolinkLiterals,
+        Construct::IndentedCode => MarkdownConstruct::IndentedCode,
+        Construct::Footnotes => MarkdownConstruct::Footnotes,
+        Construct::Math => MarkdownConstruct::Math,
+        Construct::MdxExpressions => MarkdownConstruct::MdxExpressions,
+        Construct::Strikethrough => MarkdownConstruct::Strikethrough,
+        Construct::Tables => MarkdownConstruct::Tables,
+        Construct::TaskLists => MarkdownConstruct::TaskLists,
+    }
+}
+
 fn create_file_with_args(
     template_path: &Utf8Path,
     output_path: Option<&Utf8Path>,
@@ -50,14 +231,17 @@ fn create_file_with_markdown(
     template_path: &Utf8Path,
     markdown_path: &Utf8Path,
     output_path: Option<&Utf8Path>,
-    inline_html: bool,
+    data: Option<&[(String, String)]>,
+    options: &MarkdownOptions,
 ) -> color_eyre::Result<()> {
     let template = std::fs::read_to_string(template_path)?;
     let markdown = std::fs::read_to_string(markdown_path)?;
 
-    let (frontmatter, content) = process_md_file(&markdown, inline_html)?;
-    let output =
-        render_template_with_md(&template, &frontmatter, &content, false)?;
+    let document = process_md_file(&markdown, options)?;
+    for warning in &document.warnings {
+        eprintln!("Warning: {markdown_path}: {warning}");
+    }
+    let output = render_template_with_md(&template, &document, data, false)?;
 
     match output_path {
         Some(path) => {
@@ -70,3 +254,102 @@ fn create_file_with_markdown(
 
     Ok(())
 }
+
+fn create_highlight_stylesheet(
+    theme: Option<&str>,
+    output_path: Option<&Utf8Path>,
+) -> color_eyre::Result<()> {
+    let theme = highlight_theme(theme)?;
+    let Some(output) = highlight_stylesheet(theme) else {
+        bail!("Failed to build the stylesheet of theme `{theme}`");
+    };
+
+    match output_path {
+        Some(path) => {
+            std::fs::write(path, output)?;
+        }
+        None => {
+            print!("{output}");
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interface::Cli;
+    use clap::Parser;
+    use rstest::*;
+
+    fn options(flags: &[&str]) -> color_eyre::Result<MarkdownOptions> {
+        input_options("input.md", flags)
+    }
+
+    fn input_options(
+        input: &str,
+        flags: &[&str],
+    ) -> color_eyre::Result<MarkdownOptions> {
+        let cli = Cli::try_parse_from(
+            ["jango", "markdown", "template.html", input]
+                .iter()
+                .chain(flags),
+        )?;
+        let Command::Markdown(args) = cli.command else {
+            bail!("Expected the markdown command");
+        };
+
+        markdown_options(&args)
+    }
+
+    #[rstest]
+    #[case::flag(&["--math"], true)]
+    #[case::construct(&["--enable", "math"], true)]
+    #[case::other_construct(&["--enable", "footnotes"], false)]
+    fn enabling_math_renders_it(#[case] flags: &[&str], #[case] math: bool) {
+        let options = options(flags).expect("Failed to build options");
+
+        assert_eq!(options.math, math);
+    }
+
+    #[rstest]
+    #[case::other_directory("../docs/page.md", &["--includes"], "../docs")]
+    #[case::current_directory("page.md", &["--includes"], ".")]
+    #[case::explicit_root(
+        "../docs/page.md",
+        &["--includes", "--include-root", ".."],
+        ".."
+    )]
+    fn includes_are_confined_to_the_input_directory(
+        #[case] input: &str,
+        #[case] flags: &[&str],
+        #[case] root: &str,
+    ) {
+        let options =
+            input_options(input, flags).expect("Failed to build options");
+
+        assert_eq!(
+            options.includes,
+            Some(Includes {
+                current: input.into(),
+                root: root.into(),
+            })
+        );
+    }
+
+    #[rstest]
+    #[case::default(&[], false)]
+    #[case::flag(&["--citations"], true)]
+    #[case::bibliography(&["--bibliography", "references.bib"], true)]
+    fn citations_are_opt_in(#[case] flags: &[&str], #[case] enabled: bool) {
+        let options = options(flags).expect("Failed to build options");
+
+        assert_eq!(options.citations.is_some(), enabled);
+    }
+
+    #[test]
+    fn disabling_math_conflicts_with_rendering_it() {
+        assert!(options(&["--math", "--disable", "math"]).is_err());
+    }
+}
@@ -1,6 +1,6 @@
-use super::parsing::parse_key_val;
+use super::parsing::{parse_element_attribute, parse_key_val};
 use camino::Utf8PathBuf;
-use clap::{Args, Parser, Subcommand};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 
 #[derive(Debug, Parser)]
 #[command(
@@ -31,6 +31,7 @@ pub struct Cli {
 
 {all-args}"
 ))]
+#[allow(clippy::large_enum_variant)]
 pub enum Command {
     /// Use arguments to populate the template
     #[command(arg_required_else_help = true, visible_alias = "a")]
@@ -39,6 +40,10 @@ pub enum Command {
     /// Use a Markdown file to populate the template
     #[command(arg_required_else_help = true, visible_alias = "md")]
     Markdown(MarkdownArgs),
+
+    /// Write the stylesheet for code highlighted with `--highlight classes`
+    #[command(name = "highlight-css")]
+    HighlightCss(HighlightCssArgs),
 }
 
 #[derive(Debug, Args)]
@@ -76,11 +81,191 @@ pub struct CommandLineArgs {
 
 {all-args}"
 ))]
+#[allow(clippy::struct_excessive_bools)]
 pub struct MarkdownArgs {
+    /// Render the Markdown body as a Tera template before compiling it
+    #[arg(short, long)]
+    pub render_body: bool,
+
+    /// `key=value` pairs to pass to the template and the Markdown body
+    #[arg(short, long, value_parser = parse_key_val::<String, String>)]
+    pub data: Option<Vec<(String, String)>>,
+
+    /// Splice the files referenced by `<!-- include: path -->` comments into the Markdown body
+    #[arg(long)]
+    pub includes: bool,
+
+    /// Directory that included files, and the bibliography and citation style set by the
+    /// frontmatter, have to be inside of. Defaults to the input's directory
+    #[arg(long, value_name = "DIR", value_hint = clap::ValueHint::DirPath)]
+    pub include_root: Option<Utf8PathBuf>,
+
+    /// Directory with the Tera templates that can be called as `{{< name >}}` or `{% name() %}`
+    /// shortcodes
+    #[arg(long, value_name = "DIR", value_hint = clap::ValueHint::DirPath)]
+    pub shortcodes: Option<Utf8PathBuf>,
+
+    /// Dialect of Markdown to parse
+    #[arg(long, default_value = "gfm")]
+    pub flavor: Flavor,
+
+    /// Parse a construct that the flavor doesn't include
+    #[arg(long, value_name = "CONSTRUCT")]
+    pub enable: Vec<Construct>,
+
+    /// Don't parse a construct that the flavor includes
+    #[arg(long, value_name = "CONSTRUCT")]
+    pub disable: Vec<Construct>,
+
     /// Don't escape input's inline HTML
     #[arg(short, long = "inline")]
     pub inline_html: bool,
 
+    /// Keep input's inline HTML, removing the tags, attributes and URL schemes that aren't
+    /// allowed
+    #[arg(short, long, conflicts_with = "inline_html")]
+    pub sanitize: bool,
+
+    /// Extra tag to keep when sanitizing inline HTML
+    #[arg(long, value_name = "TAG", requires = "sanitize")]
+    pub allow_tag: Vec<String>,
+
+    /// Extra attribute to keep when sanitizing inline HTML, on every tag or as `tag.attribute`
+    #[arg(long, value_name = "[TAG.]ATTRIBUTE", requires = "sanitize")]
+    pub allow_attribute: Vec<String>,
+
+    /// Extra URL scheme to keep when sanitizing inline HTML
+    #[arg(long, value_name = "SCHEME", requires = "sanitize")]
+    pub allow_scheme: Vec<String>,
+
+    /// Demote headings by this many levels, or promote them if negative
+    #[arg(
+        long,
+        value_name = "LEVELS",
+        default_value_t = 0,
+        allow_negative_numbers = true
+    )]
+    pub shift_headings: i8,
+
+    /// Add a self-link anchor to every heading
+    #[arg(short, long)]
+    pub anchors: bool,
+
+    /// Render `$...$` and `$$...$$` LaTeX formulas as math markup
+    #[arg(short, long)]
+    pub math: bool,
+
+    /// Render fenced code blocks tagged `bob` or `svgbob` as ASCII diagrams in inline SVG
+    #[arg(long)]
+    pub diagrams: bool,
+
+    /// Replace `:rocket:`-style shortcodes with their emoji
+    #[arg(long)]
+    pub emoji: bool,
+
+    /// Curl quotes and turn `--`, `---` and `...` into dashes and ellipses
+    #[arg(long)]
+    pub smart_punctuation: bool,
+
+    /// Summarize files without a `<!-- more -->` marker with this many words instead of their
+    /// first paragraph
+    #[arg(long, value_name = "N")]
+    pub summary_words: Option<usize>,
+
+    /// Resolve `[@key]` citations against the bibliography set by the frontmatter's
+    /// `bibliography` field
+    #[arg(long)]
+    pub citations: bool,
+
+    /// BibTeX, CSL-JSON or Hayagriva file to resolve `[@key]` citations against, unless the
+    /// frontmatter sets `bibliography`. Implies `--citations`
+    #[arg(long, value_name = "FILE", value_hint = clap::ValueHint::FilePath)]
+    pub bibliography: Option<Utf8PathBuf>,
+
+    /// Citation style, either the name of a bundled style or a CSL file, unless the frontmatter
+    /// sets `csl`
+    #[arg(long, value_name = "STYLE", default_value = "apa")]
+    pub csl: String,
+
+    /// Leave footnotes out of `content`, exposing them as `footnotes` and `footnotes_html`
+    #[arg(long)]
+    pub separate_footnotes: bool,
+
+    /// Render images with a title alone in their paragraph as captioned figures
+    #[arg(long)]
+    pub figures: bool,
+
+    /// Number the captions of figures, implying `--figures`
+    #[arg(long)]
+    pub number_figures: bool,
+
+    /// Label of numbered figure captions
+    #[arg(long, value_name = "LABEL", default_value = "Figure")]
+    pub figure_label: String,
+
+    /// Number sections, figures and tables, and resolve `@sec:id`, `@fig:id` and `@tbl:id`
+    /// references to them, implying `--number-figures`
+    #[arg(long)]
+    pub cross_references: bool,
+
+    /// Label of numbered sections in cross-references
+    #[arg(long, value_name = "LABEL", default_value = "Section")]
+    pub section_label: String,
+
+    /// Label of numbered tables in cross-references and table captions
+    #[arg(long, value_name = "LABEL", default_value = "Table")]
+    pub table_label: String,
+
+    /// Also split the contents into `sections`, one for each heading, keyed by id, and into the
+    /// `section_list` of the same sections in document order
+    #[arg(long)]
+    pub sections: bool,
+
+    /// Also split the contents into `slides` at every `---` and level 2 heading
+    #[arg(long)]
+    pub slides: bool,
+
+    /// Render `> [!NOTE]` callouts and `:::note` admonitions
+    #[arg(short, long)]
+    pub callouts: bool,
+
+    /// Class of rendered callouts, also used as the prefix of their other classes
+    #[arg(long, value_name = "CLASS", default_value = "callout")]
+    pub callout_class: String,
+
+    /// `kind=title` pairs with the default title of each kind of callout
+    #[arg(long, value_name = "KIND=TITLE", value_parser = parse_key_val::<String, String>)]
+    pub callout_title: Vec<(String, String)>,
+
+    /// Point relative links to `.md` files to their `.html` output
+    #[arg(long)]
+    pub html_links: bool,
+
+    /// Prefix relative link and image URLs with this URL or path
+    #[arg(long, value_name = "URL")]
+    pub base_url: Option<String>,
+
+    /// `element.attribute=value` triples with extra attributes of the HTML elements, like
+    /// `img.loading=lazy`. Use `a:external` for links to other sites
+    #[arg(long, value_name = "ELEMENT.ATTRIBUTE=VALUE", value_parser = parse_element_attribute)]
+    pub attribute: Vec<(String, String, String)>,
+
+    /// Wrap tables in a `<div>` with this class
+    #[arg(long, value_name = "CLASS")]
+    pub wrap_tables: Option<String>,
+
+    /// Resolve `[[wikilinks]]` against the files in this directory
+    #[arg(long, value_name = "DIR", value_hint = clap::ValueHint::DirPath)]
+    pub vault: Option<Utf8PathBuf>,
+
+    /// Highlight the syntax of fenced code blocks
+    #[arg(long, value_name = "STYLE")]
+    pub highlight: Option<HighlightStyle>,
+
+    /// Color theme for highlighting with inline styles. Defaults to `InspiredGitHub`
+    #[arg(long, value_name = "THEME", requires = "highlight")]
+    pub theme: Option<String>,
+
     /// Path to the template file
     #[arg(value_hint = clap::ValueHint::FilePath)]
     pub template: Utf8PathBuf,
@@ -92,3 +277,60 @@ pub struct MarkdownArgs {
     /// Path for the output file. Prints to stdout if not present
     pub output: Option<Utf8PathBuf>,
 }
+
+#[derive(Debug, Args)]
+#[command(help_template(
+    "\
+{name}
+{about-with-newline}
+{usage-heading} {usage}
+
+{all-args}"
+))]
+pub struct HighlightCssArgs {
+    /// Color theme to take the colors from. Defaults to `InspiredGitHub`
+    #[arg(long, value_name = "THEME")]
+    pub theme: Option<String>,
+
+    /// Path for the output file. Prints to stdout if not present
+    pub output: Option<Utf8PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Flavor {
+    /// GitHub Flavored Markdown
+    Gfm,
+    /// Strict `CommonMark`
+    #[value(name = "commonmark")]
+    CommonMark,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Construct {
+    /// Links between angle brackets
+    Autolinks,
+    /// Bare URLs and emails
+    AutolinkLiterals,
+    /// Code blocks indented by four spaces
+    IndentedCode,
+    /// Footnote references and definitions
+    Footnotes,
+    /// `$...$` and `$$...$$` formulas
+    Math,
+    /// `{...}` expressions
+    MdxExpressions,
+    /// `~~deleted~~` text
+    Strikethrough,
+    /// Tables
+    Tables,
+    /// `[ ]` and `[x]` task list checkboxes
+    TaskLists,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum HighlightStyle {
+    /// Color tokens with inline `style` attributes
+    Inline,
+    /// Mark tokens with CSS classes
+    Classes,
+}
@@ -1,3 +1,4 @@
+use crate::templates::check_attribute;
 use std::error::Error;
 
 /// Parse a single key-value pair
@@ -15,3 +16,75 @@ where
         .ok_or_else(|| format!("invalid KEY=value: no `=` found in `{s}`"))?;
     Ok((s[..pos].parse()?, s[pos + 1..].parse()?))
 }
+
+/// Parse an `element.attribute=value` triple
+pub fn parse_element_attribute(
+    s: &str,
+) -> Result<(String, String, String), Box<dyn Error + Send + Sync + 'static>> {
+    let (key, value) = parse_key_val::<String, String>(s)?;
+    let (element, name) = key.rsplit_once('.').ok_or_else(|| {
+        format!("invalid ELEMENT.ATTRIBUTE=value: no `.` found in `{key}`")
+    })?;
+    if element.is_empty() {
+        return Err(format!(
+            "invalid ELEMENT.ATTRIBUTE=value: no element in `{key}`"
+        )
+        .into());
+    }
+    check_attribute(element, name)?;
+
+    Ok((element.to_string(), name.to_string(), value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    #[rstest]
+    #[case::simple("key=value", ("key", "value"))]
+    #[case::empty_value("key=", ("key", ""))]
+    #[case::equals_in_value("key=a=b", ("key", "a=b"))]
+    fn key_value_pairs_are_parsed(
+        #[case] input: &str,
+        #[case] expected: (&str, &str),
+    ) {
+        let (key, value) = parse_key_val::<String, String>(input)
+            .expect("Failed to parse key-value pair");
+
+        assert_eq!((key.as_str(), value.as_str()), expected);
+    }
+
+    #[test]
+    fn key_value_pairs_require_an_equals_sign() {
+        assert!(parse_key_val::<String, String>("key").is_err());
+    }
+
+    #[rstest]
+    #[case::simple("img.loading=lazy", ("img", "loading", "lazy"))]
+    #[case::external_links("a:external.rel=noopener", ("a:external", "rel", "noopener"))]
+    #[case::dashed_name("pre.data-copy=", ("pre", "data-copy", ""))]
+    #[case::equals_in_value("a.title=a=b", ("a", "title", "a=b"))]
+    fn element_attributes_are_parsed(
+        #[case] input: &str,
+        #[case] expected: (&str, &str, &str),
+    ) {
+        let (element, name, value) = parse_element_attribute(input)
+            .expect("Failed to parse element attribute");
+
+        assert_eq!((element.as_str(), name.as_str(), value.as_str()), expected);
+    }
+
+    #[rstest]
+    #[case::no_equals("img.loading")]
+    #[case::no_dot("img=lazy")]
+    #[case::no_element(".class=x")]
+    #[case::no_name("img.=lazy")]
+    #[case::invalid_name("img.on load=x")]
+    #[case::quote_in_name("img.a\"b=x")]
+    #[case::heading_id("h2.id=intro")]
+    #[case::unsupported_element("div.class=x")]
+    fn invalid_element_attributes_are_errors(#[case] input: &str) {
+        assert!(parse_element_attribute(input).is_err());
+    }
+}
@@ -0,0 +1,513 @@
+use super::{extraction::frontmatter_length, html_element};
+use markdown::mdast::{Blockquote, Node, Paragraph, Text};
+use std::{borrow::Cow, collections::BTreeMap};
+
+/// Settings for how callouts and admonitions are rendered.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Callouts {
+    /// Class of the callouts' `<div>`, which is also used as the prefix of the kind and title
+    /// classes.
+    pub class: String,
+    /// Default titles of each kind of callout. Kinds without one are titled after their name.
+    pub titles: BTreeMap<String, String>,
+}
+
+impl Default for Callouts {
+    fn default() -> Self {
+        Self {
+            class: "callout".to_string(),
+            titles: BTreeMap::new(),
+        }
+    }
+}
+
+impl Callouts {
+    fn title(&self, kind: &str) -> String {
+        if let Some(title) = self.titles.get(kind) {
+            return title.clone();
+        }
+
+        let mut chars = kind.chars();
+        chars.next().map_or_else(String::new, |first| {
+            first.to_uppercase().chain(chars).collect()
+        })
+    }
+}
+
+/// Rewrites the `:::kind title` fenced admonitions of a Markdown `&str` into `> [!kind] title`
+/// callouts.
+///
+/// This lets them be parsed as blockquotes. The `>` markers are written after the indentation and
+/// the blockquote markers of the opening fence, so admonitions stay inside the list items and
+/// blockquotes they're written in. Fences inside code blocks and the frontmatter are left
+/// untouched.
+#[must_use]
+pub fn expand_md_admonitions(input: &str) -> Cow<'_, str> {
+    if !input.contains(":::") {
+        return Cow::Borrowed(input);
+    }
+
+    let (frontmatter, body) = input.split_at(frontmatter_length(input));
+    let mut output = String::with_capacity(input.len());
+    output.push_str(frontmatter);
+
+    let mut depth = 0;
+    // Indentation and blockquote markers of the outermost open admonition.
+    let mut container = "";
+    let mut code_blocks = CodeBlocks::new();
+    for line in body.split_inclusive('\n') {
+        let content = line.trim_end_matches(['\r', '\n']);
+        let line_ending = &line[content.len()..];
+        let (line_container, rest) = if depth > 0 {
+            (container, strip_container(content, container))
+        } else {
+            split_container(content)
+        };
+        let trimmed = rest.trim();
+
+        let code = code_blocks.contains(content);
+        if !code && let Some((kind, title)) = admonition_start(trimmed) {
+            container = line_container;
+            output.push_str(container);
+            output.push_str(&"> ".repeat(depth));
+            output.push_str(format!("> [!{kind}] {title}").trim_end());
+            output.push_str(line_ending);
+            depth += 1;
+            continue;
+        } else if !code && depth > 0 && is_admonition_end(trimmed) {
+            depth -= 1;
+            output.push_str(
+                (container.to_string() + &"> ".repeat(depth)).trim_end(),
+            );
+            output.push_str(line_ending);
+            continue;
+        }
+
+        if depth > 0 {
+            let prefix = container.to_string() + &"> ".repeat(depth);
+            if trimmed.is_empty() {
+                output.push_str(prefix.trim_end());
+            } else {
+                output.push_str(&prefix);
+                output.push_str(rest);
+            }
+            output.push_str(line_ending);
+        } else {
+            output.push_str(line);
+        }
+    }
+
+    Cow::Owned(output)
+}
+
+/// Splits a line into its indentation and blockquote markers, and the rest of it.
+fn split_container(line: &str) -> (&str, &str) {
+    let rest = line.trim_start_matches(['>', ' ', '\t']);
+
+    line.split_at(line.len() - rest.len())
+}
+
+/// Strips the indentation and blockquote markers of `container` from a line, as far as the line
+/// starts with them.
+fn strip_container<'a>(line: &'a str, container: &str) -> &'a str {
+    let mut rest = line;
+    for c in container.chars() {
+        if c == '>' {
+            match rest.trim_start_matches([' ', '\t']).strip_prefix('>') {
+                Some(after) => rest = after,
+                None => break,
+            }
+        } else {
+            rest = rest.strip_prefix([' ', '\t']).unwrap_or(rest);
+        }
+    }
+
+    rest
+}
+
+/// Follows the code blocks of a Markdown `&str` line by line, so text scanners can leave them
+/// alone.
+///
+/// Knows about fenced code and about indented code, both at the top level and inside list items.
+/// Other containers, such as blockquotes, aren't taken into account.
+#[derive(Debug)]
+pub(super) struct CodeBlocks {
+    fence: Option<String>,
+    indented: bool,
+    /// Columns where the contents of the open list items start.
+    list_items: Vec<usize>,
+    /// Whether the previous line was blank, which lets indented code start.
+    after_blank: bool,
+}
+
+impl CodeBlocks {
+    pub(super) const fn new() -> Self {
+        Self {
+            fence: None,
+            indented: false,
+            list_items: Vec::new(),
+            after_blank: true,
+        }
+    }
+
+    /// Returns whether `line`, the next line of the input, is part of a code block, fences
+    /// included.
+    pub(super) fn contains(&mut self, line: &str) -> bool {
+        let trimmed = line.trim();
+        if let Some(fence) = &self.fence {
+            if code_fence_start(trimmed).is_some_and(|end| {
+                end.starts_with(fence.as_str()) && end.len() == trimmed.len()
+            }) {
+                self.fence = None;
+            }
+            return true;
+        }
+        if trimmed.is_empty() {
+            self.after_blank = true;
+            return self.indented;
+        }
+
+        let indent = indentation(line);
+        let after_blank = std::mem::replace(&mut self.after_blank, false);
+        if after_blank {
+            self.close_list_items(indent);
+        }
+        let base = self.list_items.last().copied().unwrap_or(0);
+        if indent >= base + 4 {
+            // Without a blank line before it, an indented line continues a paragraph.
+            self.indented |= after_blank;
+            return self.indented;
+        }
+
+        self.indented = false;
+        if let Some(fence) = code_fence_start(trimmed) {
+            self.fence = Some(fence);
+            return true;
+        }
+        if let Some(width) = list_marker_width(trimmed) {
+            self.close_list_items(indent + 1);
+            self.list_items.push(indent + width);
+        }
+
+        false
+    }
+
+    fn close_list_items(&mut self, indent: usize) {
+        while self
+            .list_items
+            .last()
+            .is_some_and(|column| indent < *column)
+        {
+            self.list_items.pop();
+        }
+    }
+}
+
+/// Returns the width of a line's indentation, with tabs stopping every 4 columns.
+fn indentation(line: &str) -> usize {
+    let mut width = 0;
+    for c in line.chars() {
+        match c {
+            ' ' => width += 1,
+            '\t' => width += 4 - width % 4,
+            _ => break,
+        }
+    }
+
+    width
+}
+
+/// Returns the width of the list marker that `line` starts with, and of the spaces after it.
+fn list_marker_width(line: &str) -> Option<usize> {
+    let digits = line.len()
+        - line.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    let marker = match digits {
+        0 => line.starts_with(['-', '*', '+']).then_some(1)?,
+        1..=9 => line[digits..]
+            .starts_with(['.', ')'])
+            .then_some(digits + 1)?,
+        _ => return None,
+    };
+    let rest = &line[marker..];
+    let spaces = rest.len() - rest.trim_start_matches(' ').len();
+
+    match spaces {
+        _ if rest.is_empty() => Some(marker + 1),
+        1..=4 => Some(marker + spaces),
+        0 => None,
+        _ => Some(marker + 1),
+    }
+}
+
+fn code_fence_start(line: &str) -> Option<String> {
+    ['`', '~'].into_iter().find_map(|marker| {
+        let length = line.len() - line.trim_start_matches(marker).len();
+        (length >= 3).then(|| marker.to_string().repeat(length))
+    })
+}
+
+fn admonition_start(line: &str) -> Option<(&str, &str)> {
+    let rest = line
+        .strip_prefix(":::")?
+        .trim_start_matches(':')
+        .trim_start();
+    let end = rest
+        .find(|c: char| !(c.is_alphanumeric() || c == '-' || c == '_'))
+        .unwrap_or(rest.len());
+
+    (end > 0).then(|| (&rest[..end], rest[end..].trim()))
+}
+
+fn is_admonition_end(line: &str) -> bool {
+    line.len() >= 3 && line.chars().all(|c| c == ':')
+}
+
+/// Replaces the blockquotes of a Markdown ast that start with a `[!kind]` marker with callouts:
+/// a `<div>` with the `class` and `class-kind` classes, holding a title and the blockquote's
+/// contents.
+///
+/// The title is the text after the marker, or the default title for the kind when there is none.
+pub fn transform_md_callouts(root: &mut Node, callouts: &Callouts) {
+    if let Node::Blockquote(blockquote) = root
+        && let Some(callout) = callout(blockquote, callouts)
+    {
+        *root = callout;
+    }
+
+    for child in root.children_mut().into_iter().flatten() {
+        transform_md_callouts(child, callouts);
+    }
+}
+
+fn callout(blockquote: &mut Blockquote, callouts: &Callouts) -> Option<Node> {
+    let Some(Node::Paragraph(paragraph)) = blockquote.children.first() else {
+        return None;
+    };
+    let Some(Node::Text(text)) = paragraph.children.first() else {
+        return None;
+    };
+    let (kind, rest) = callout_marker(&text.value)?;
+    let kind = kind.to_lowercase();
+    let rest = rest.to_string();
+
+    let mut children = std::mem::take(&mut blockquote.children);
+    let Node::Paragraph(paragraph) = children.remove(0) else {
+        unreachable!("The first child was checked to be a paragraph");
+    };
+
+    let mut title = Vec::new();
+    let mut body = Vec::new();
+    let mut in_title = true;
+    let inlines = std::iter::once(Node::Text(Text {
+        value: rest,
+        position: None,
+    }))
+    .chain(paragraph.children.into_iter().skip(1));
+
+    for node in inlines {
+        match node {
+            Node::Text(text) if in_title => {
+                let (line, next) = text
+                    .value
+                    .split_once('\n')
+                    .map_or((text.value.as_str(), None), |(line, next)| {
+                        (line, Some(next))
+                    });
+                push_text(&mut title, line);
+                if let Some(next) = next {
+                    push_text(&mut body, next);
+                    in_title = false;
+                }
+            }
+            Node::Break(_) if in_title => in_title = false,
+            node if in_title => title.push(node),
+            node => body.push(node),
+        }
+    }
+
+    trim_title(&mut title);
+    if title.is_empty() {
+        push_text(&mut title, &callouts.title(&kind));
+    }
+    if !body.is_empty() {
+        children.insert(
+            0,
+            Node::Paragraph(Paragraph {
+                children: body,
+                position: paragraph.position,
+            }),
+        );
+    }
+
+    let class = &callouts.class;
+    children.insert(
+        0,
+        html_element("p", &[("class", &format!("{class}-title"))], title),
+    );
+
+    Some(html_element(
+        "div",
+        &[("class", &format!("{class} {class}-{kind}"))],
+        children,
+    ))
+}
+
+fn callout_marker(text: &str) -> Option<(&str, &str)> {
+    let rest = text.strip_prefix("[!")?;
+    let end = rest.find(']')?;
+    let kind = &rest[..end];
+    if kind.is_empty()
+        || !kind
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+    {
+        return None;
+    }
+
+    Some((kind, rest[end + 1..].trim_start_matches(['+', '-'])))
+}
+
+fn push_text(nodes: &mut Vec<Node>, value: &str) {
+    if !value.is_empty() {
+        nodes.push(Node::Text(Text {
+            value: value.to_string(),
+            position: None,
+        }));
+    }
+}
+
+fn trim_title(title: &mut Vec<Node>) {
+    if let Some(Node::Text(text)) = title.first_mut() {
+        text.value = text.value.trim_start().to_string();
+    }
+    if let Some(Node::Text(text)) = title.last_mut() {
+        text.value = text.value.trim_end().to_string();
+    }
+    title.retain(
+        |node| !matches!(node, Node::Text(text) if text.value.is_empty()),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::templates::{
+        MarkdownOptions, collect_md_headings, parse_md, render_md_html,
+    };
+    use rstest::*;
+
+    fn render(input: &str, callouts: Callouts) -> String {
+        let options = MarkdownOptions {
+            callouts: Some(callouts),
+            ..MarkdownOptions::default()
+        };
+        let mut ast =
+            parse_md(input, &options).expect("Failed to parse markdown input");
+        let headings = collect_md_headings(&mut ast);
+
+        render_md_html(&ast, &headings, &options)
+    }
+
+    #[rstest]
+    #[case::default_title(
+        "> [!NOTE]\n> Some *text*.",
+        "<div class=\"callout callout-note\">\n\
+        <p class=\"callout-title\">Note</p>\n\
+        <p>Some <em>text</em>.</p>\n</div>"
+    )]
+    #[case::custom_title(
+        "> [!warning] Be *careful*\n> Some text.\n>\n> - A list",
+        "<div class=\"callout callout-warning\">\n\
+        <p class=\"callout-title\">Be <em>careful</em></p>\n\
+        <p>Some text.</p>\n<ul>\n<li>A list</li>\n</ul>\n</div>"
+    )]
+    #[case::only_title(
+        "> [!TIP]- Folded",
+        "<div class=\"callout callout-tip\">\n\
+        <p class=\"callout-title\">Folded</p>\n</div>"
+    )]
+    #[case::plain_blockquote(
+        "> [!] Not a callout",
+        "<blockquote>\n<p>[!] Not a callout</p>\n</blockquote>"
+    )]
+    fn callouts_render_to_divs(#[case] input: &str, #[case] expected: &str) {
+        assert_eq!(render(input, Callouts::default()), expected);
+    }
+
+    #[test]
+    fn callouts_use_configured_classes_and_titles() {
+        let callouts = Callouts {
+            class: "admonition".to_string(),
+            titles: BTreeMap::from([("note".to_string(), "Nota".to_string())]),
+        };
+
+        assert_eq!(
+            render("> [!NOTE]\n> Text", callouts),
+            "<div class=\"admonition admonition-note\">\n\
+            <p class=\"admonition-title\">Nota</p>\n<p>Text</p>\n</div>"
+        );
+    }
+
+    #[test]
+    fn admonitions_render_as_callouts() {
+        let input =
+            ":::tip Title\nSome text.\n\n:::danger\nNested\n:::\n:::\nAfter";
+
+        assert_eq!(
+            render(input, Callouts::default()),
+            "<div class=\"callout callout-tip\">\n\
+            <p class=\"callout-title\">Title</p>\n<p>Some text.</p>\n\
+            <div class=\"callout callout-danger\">\n\
+            <p class=\"callout-title\">Danger</p>\n<p>Nested</p>\n</div>\n\
+            </div>\n<p>After</p>"
+        );
+    }
+
+    #[rstest]
+    #[case::list_item(
+        "- item\n\n  :::note\n  inside\n  :::\n- next",
+        "<ul>\n<li>\n<p>item</p>\n<div class=\"callout callout-note\">\n\
+        <p class=\"callout-title\">Note</p>\n<p>inside</p>\n</div>\n</li>\n\
+        <li>\n<p>next</p>\n</li>\n</ul>"
+    )]
+    #[case::nested_in_list_item(
+        "1. item\n\n   :::tip\n   :::warning\n   inside\n   :::\n   :::\n\nafter",
+        "<ol>\n<li>\n<p>item</p>\n<div class=\"callout callout-tip\">\n\
+        <p class=\"callout-title\">Tip</p>\n<div class=\"callout callout-warning\">\n\
+        <p class=\"callout-title\">Warning</p>\n<p>inside</p>\n</div>\n</div>\n\
+        </li>\n</ol>\n<p>after</p>"
+    )]
+    #[case::blockquote(
+        "> quote\n>\n> :::note\n> inside\n>\n> more\n> :::\n>\n> after",
+        "<blockquote>\n<p>quote</p>\n<div class=\"callout callout-note\">\n\
+        <p class=\"callout-title\">Note</p>\n<p>inside</p>\n<p>more</p>\n</div>\n\
+        <p>after</p>\n</blockquote>"
+    )]
+    #[case::list_in_blockquote(
+        "> - item\n>\n>   :::note\n>   inside\n>   :::",
+        "<blockquote>\n<ul>\n<li>\n<p>item</p>\n<div class=\"callout callout-note\">\n\
+        <p class=\"callout-title\">Note</p>\n<p>inside</p>\n</div>\n</li>\n</ul>\n\
+        </blockquote>"
+    )]
+    fn admonitions_stay_in_their_container(
+        #[case] input: &str,
+        #[case] expected: &str,
+    ) {
+        assert_eq!(render(input, Callouts::default()), expected);
+    }
+
+    #[rstest]
+    #[case::code_block("```\n:::tip\n```\n", "```\n:::tip\n```\n")]
+    #[case::indented_code("Text\n\n    :::tip\n", "Text\n\n    :::tip\n")]
+    #[case::frontmatter(
+        "+++\ntitle = \":::tip\"\n+++\n:::note\nText\n:::\n",
+        "+++\ntitle = \":::tip\"\n+++\n> [!note]\n> Text\n\n"
+    )]
+    #[case::no_admonitions("Some text", "Some text")]
+    fn admonitions_skip_code_and_frontmatter(
+        #[case] input: &str,
+        #[case] expected: &str,
+    ) {
+        assert_eq!(expand_md_admonitions(input), expected);
+    }
+}
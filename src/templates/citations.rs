@@ -0,0 +1,770 @@
+use super::{
+    SAFE_HREF_PROTOCOLS, encode, sanitize_url, trusted_html, trusted_html_node,
+};
+use camino::{Utf8Path, Utf8PathBuf};
+use color_eyre::eyre::{Context, bail, eyre};
+use hayagriva::{
+    BibliographyDriver, BibliographyRequest, CitationItem, CitationRequest,
+    ElemChild, ElemChildren, Formatting, Library, LocatorPayload, Rendered,
+    SpecificLocator,
+    archive::{ArchivedStyle, locales},
+    citationberg::{
+        FontStyle, FontVariant, FontWeight, IndependentStyle, Locale, Style,
+        TextDecoration, VerticalAlign, json::Item, taxonomy::Locator,
+    },
+    io::{from_biblatex_str, from_yaml_str},
+};
+use markdown::mdast::{Node, Text};
+
+const PLACEHOLDER_START: char = '\u{E000}';
+const PLACEHOLDER_END: char = '\u{E001}';
+
+/// Settings for how `[@key]` citations are resolved and formatted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Citations {
+    /// Bibliography to cite from when the frontmatter doesn't set one.
+    pub bibliography: Option<Utf8PathBuf>,
+    /// Directory that the paths set in the frontmatter are relative to.
+    pub directory: Utf8PathBuf,
+    /// Directory that the files set in the frontmatter have to be inside of.
+    pub root: Utf8PathBuf,
+    /// Citation style to use when the frontmatter doesn't set one: the name of a bundled style,
+    /// such as `apa` or `ieee`, or the path to a CSL file.
+    pub style: String,
+}
+
+impl Default for Citations {
+    fn default() -> Self {
+        Self {
+            bibliography: None,
+            directory: Utf8PathBuf::new(),
+            root: Utf8PathBuf::from("."),
+            style: "apa".to_string(),
+        }
+    }
+}
+
+impl Citations {
+    /// Loads the bibliography set by the `bibliography` field of `frontmatter`, or the default
+    /// one, in the style set by its `csl` field or the default one.
+    ///
+    /// Returns `None` if there's no bibliography to cite from.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the bibliography or the style can't be read or parsed, or if the
+    /// frontmatter sets one outside of the root.
+    pub fn load(
+        &self,
+        frontmatter: &toml::Table,
+    ) -> color_eyre::Result<Option<Bibliography>> {
+        let field = |name: &str| {
+            frontmatter
+                .get(name)
+                .map(|value| {
+                    value.as_str().ok_or_else(|| {
+                        eyre!("The `{name}` field must be a string")
+                    })
+                })
+                .transpose()
+        };
+
+        let bibliography = match field("bibliography")? {
+            Some(path) => self.confine(path)?,
+            None => match &self.bibliography {
+                Some(path) => path.clone(),
+                None => return Ok(None),
+            },
+        };
+        let style = match field("csl")? {
+            Some(style) if ArchivedStyle::by_name(style).is_some() => {
+                load_style(style)?
+            }
+            Some(style) => load_style(self.confine(style)?.as_str())?,
+            None => load_style(&self.style)?,
+        };
+
+        Bibliography::load(&bibliography, style).map(Some)
+    }
+
+    /// Resolves a path set in the frontmatter, making sure that it's inside of the root.
+    fn confine(&self, path: &str) -> color_eyre::Result<Utf8PathBuf> {
+        let path = self.directory.join(path);
+        let root = self.root.canonicalize_utf8().wrap_err_with(|| {
+            format!("Failed to open citations root `{}`", self.root)
+        })?;
+        let canonical = path
+            .canonicalize_utf8()
+            .wrap_err_with(|| format!("Failed to read `{path}`"))?;
+        if !canonical.starts_with(&root) {
+            bail!("`{path}` is outside of the citations root `{root}`");
+        }
+
+        Ok(canonical)
+    }
+}
+
+/// Loads the bundled citation style called `name`, or the CSL file at the path `name`.
+fn load_style(name: &str) -> color_eyre::Result<IndependentStyle> {
+    let style = if let Some(style) = ArchivedStyle::by_name(name) {
+        style.get()
+    } else {
+        let xml = std::fs::read_to_string(name).wrap_err_with(|| {
+            format!(
+                "`{name}` is neither a bundled citation style nor a CSL file"
+            )
+        })?;
+        Style::from_xml(&xml).wrap_err_with(|| {
+            format!("Failed to parse citation style `{name}`")
+        })?
+    };
+
+    match style {
+        Style::Independent(style) => Ok(style),
+        Style::Dependent(_) => {
+            bail!("`{name}` is a dependent citation style")
+        }
+    }
+}
+
+/// The entries that citations refer to, with the style to format them in.
+#[derive(Debug)]
+pub struct Bibliography {
+    entries: Entries,
+    style: IndependentStyle,
+    locales: Vec<Locale>,
+}
+
+#[derive(Debug)]
+enum Entries {
+    Library(Library),
+    CslJson(Vec<Item>),
+}
+
+/// Formats `groups` into citations and a bibliography, looking their entries up with `$find`.
+///
+/// This is a macro because the bound of the entries formatted by `BibliographyDriver` is private.
+macro_rules! render_with {
+    ($bibliography:expr, $groups:expr, $find:expr) => {{
+        let mut driver = BibliographyDriver::new();
+        for group in $groups {
+            let items = group
+                .iter()
+                .filter_map(|cite: &Cite| {
+                    Some(CitationItem::with_locator(
+                        $find(cite.key.as_str())?,
+                        cite.locator(),
+                    ))
+                })
+                .collect();
+            driver.citation(CitationRequest::from_items(
+                items,
+                &$bibliography.style,
+                &$bibliography.locales,
+            ));
+        }
+
+        driver.finish(BibliographyRequest::new(
+            &$bibliography.style,
+            None,
+            &$bibliography.locales,
+        ))
+    }};
+}
+
+impl Bibliography {
+    /// Reads the entries of a BibTeX or BibLaTeX file, of a CSL-JSON file if its extension is
+    /// `.json` or of a Hayagriva file if it's `.yml` or `.yaml`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file can't be read or parsed.
+    pub fn load(
+        path: &Utf8Path,
+        style: IndependentStyle,
+    ) -> color_eyre::Result<Self> {
+        let source = std::fs::read_to_string(path).wrap_err_with(|| {
+            format!("Failed to read bibliography `{path}`")
+        })?;
+        let failed = || format!("Failed to parse bibliography `{path}`");
+
+        let entries = match path.extension() {
+            Some("json") => Entries::CslJson(
+                serde_json::from_str(&source).wrap_err_with(failed)?,
+            ),
+            Some("yml" | "yaml") => {
+                Entries::Library(from_yaml_str(&source).wrap_err_with(failed)?)
+            }
+            _ => Entries::Library(from_biblatex_str(&source).map_err(
+                |errors| {
+                    let errors: Vec<_> =
+                        errors.iter().map(ToString::to_string).collect();
+                    eyre!("{}: {}", failed(), errors.join(", "))
+                },
+            )?),
+        };
+
+        Ok(Self {
+            entries,
+            style,
+            locales: locales(),
+        })
+    }
+
+    fn contains(&self, key: &str) -> bool {
+        match &self.entries {
+            Entries::Library(library) => library.get(key).is_some(),
+            Entries::CslJson(items) => {
+                items.iter().any(|item| item.id().as_deref() == Some(key))
+            }
+        }
+    }
+
+    fn render(&self, groups: &[Vec<Cite>]) -> Rendered {
+        match &self.entries {
+            Entries::Library(library) => {
+                render_with!(self, groups, |key| library.get(key))
+            }
+            Entries::CslJson(items) => render_with!(self, groups, |key| {
+                items.iter().find(|item| item.id().as_deref() == Some(key))
+            }),
+        }
+    }
+}
+
+/// A reference to an entry inside a citation, with an optional locator such as `p. 12`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Cite {
+    key: String,
+    locator: Option<(Locator, String)>,
+}
+
+impl Cite {
+    fn locator(&self) -> Option<SpecificLocator<'_>> {
+        self.locator.as_ref().map(|(locator, value)| {
+            SpecificLocator(*locator, LocatorPayload::Str(value))
+        })
+    }
+}
+
+/// Replaces the `[@key]` citations of a Markdown ast with their formatted text and returns the
+/// compiled HTML of the bibliography listing the cited entries.
+///
+/// A citation can refer to several entries, as in `[@one; @two]`, and add a locator to each, as
+/// in `[@key, p. 12]`. Citations of keys that aren't in the bibliography are left as text, and a
+/// warning is returned for each of them.
+pub fn resolve_md_citations(
+    root: &mut Node,
+    bibliography: &Bibliography,
+) -> (String, Vec<String>) {
+    let mut citations = CollectedCitations::default();
+    collect_citations(root, bibliography, &mut citations);
+    let CollectedCitations { groups, warnings } = citations;
+    if groups.is_empty() {
+        return (String::new(), warnings);
+    }
+
+    let rendered = bibliography.render(&groups);
+    let citations: Vec<_> = rendered
+        .citations
+        .iter()
+        .zip(&groups)
+        .map(|(citation, group)| {
+            let keys: Vec<_> =
+                group.iter().map(|cite| cite.key.as_str()).collect();
+            let mut html = format!(
+                "<span class=\"citation\" data-cites=\"{}\">",
+                encode(&keys.join(" "))
+            );
+            write_html(&citation.citation, &mut html);
+            html.push_str("</span>");
+            html
+        })
+        .collect();
+    fill_placeholders(root, &citations);
+
+    let mut html = String::from("<div class=\"references\">\n");
+    for item in rendered
+        .bibliography
+        .iter()
+        .flat_map(|bibliography| &bibliography.items)
+    {
+        html.push_str("<div id=\"ref-");
+        html.push_str(&encode(&item.key));
+        html.push_str("\" class=\"reference\">");
+        if let Some(first_field) = &item.first_field {
+            html.push_str("<span class=\"reference-label\">");
+            write_html(&ElemChildren(vec![first_field.clone()]), &mut html);
+            html.push_str("</span> ");
+        }
+        write_html(&item.content, &mut html);
+        html.push_str("</div>\n");
+    }
+    html.push_str("</div>\n");
+
+    (html, warnings)
+}
+
+/// The citations found in a Markdown ast, in order, and the problems found with them.
+#[derive(Debug, Default)]
+struct CollectedCitations {
+    groups: Vec<Vec<Cite>>,
+    warnings: Vec<String>,
+}
+
+fn collect_citations(
+    node: &mut Node,
+    bibliography: &Bibliography,
+    citations: &mut CollectedCitations,
+) {
+    let Some(children) = node.children_mut() else {
+        return;
+    };
+
+    let mut index = 0;
+    while index < children.len() {
+        if let Node::Text(text) = &children[index]
+            && let Some(nodes) =
+                split_citations(&text.value, bibliography, citations)
+        {
+            let count = nodes.len();
+            children.splice(index..=index, nodes);
+            index += count;
+        } else {
+            collect_citations(&mut children[index], bibliography, citations);
+            index += 1;
+        }
+    }
+}
+
+/// Splits a text into text and citation placeholder nodes, or returns `None` if it holds no
+/// citations.
+fn split_citations(
+    text: &str,
+    bibliography: &Bibliography,
+    citations: &mut CollectedCitations,
+) -> Option<Vec<Node>> {
+    let mut nodes = Vec::new();
+    let mut rest = text;
+    let mut start = 0;
+    while let Some(open) = rest[start..].find("[@").map(|open| start + open) {
+        let Some(close) = rest[open..].find(']').map(|close| open + close)
+        else {
+            break;
+        };
+
+        let Some(group) = parse_citation(&rest[open + 1..close]) else {
+            start = open + 2;
+            continue;
+        };
+        let missing: Vec<_> = group
+            .iter()
+            .filter(|cite| !bibliography.contains(&cite.key))
+            .map(|cite| format!("`{}`", cite.key))
+            .collect();
+        if !missing.is_empty() {
+            citations.warnings.push(format!(
+                "Citation `{}` refers to keys that aren't in the bibliography: {}",
+                &rest[open..=close],
+                missing.join(", ")
+            ));
+            start = open + 2;
+            continue;
+        }
+
+        if open > 0 {
+            nodes.push(Node::Text(Text {
+                value: rest[..open].to_string(),
+                position: None,
+            }));
+        }
+        nodes.push(trusted_html(format!(
+            "{PLACEHOLDER_START}{}{PLACEHOLDER_END}",
+            citations.groups.len()
+        )));
+        citations.groups.push(group);
+        rest = &rest[close + 1..];
+        start = 0;
+    }
+
+    if nodes.is_empty() {
+        return None;
+    }
+    if !rest.is_empty() {
+        nodes.push(Node::Text(Text {
+            value: rest.to_string(),
+            position: None,
+        }));
+    }
+
+    Some(nodes)
+}
+
+/// Parses the `@key, locator; @key` contents of a citation.
+fn parse_citation(contents: &str) -> Option<Vec<Cite>> {
+    contents
+        .split(';')
+        .map(|cite| {
+            let cite = cite.trim().strip_prefix('@')?;
+            let end = cite
+                .find(|c: char| !(c.is_alphanumeric() || "-_:./".contains(c)))
+                .unwrap_or(cite.len());
+            let key = cite[..end].trim_end_matches(['.', ':']);
+            if key.is_empty() {
+                return None;
+            }
+
+            let rest = cite[key.len()..].trim();
+            let locator = if rest.is_empty() {
+                None
+            } else {
+                Some(parse_locator(rest.strip_prefix(',')?.trim()))
+            };
+
+            Some(Cite {
+                key: key.to_string(),
+                locator,
+            })
+        })
+        .collect()
+}
+
+fn parse_locator(locator: &str) -> (Locator, String) {
+    let (term, value) = locator
+        .split_once(char::is_whitespace)
+        .unwrap_or(("", locator));
+    let kind = match term {
+        "p." | "pp." | "page" | "pages" => Locator::Page,
+        "ch." | "chap." | "chapter" => Locator::Chapter,
+        "sec." | "section" | "§" => Locator::Section,
+        "fig." | "figure" => Locator::Figure,
+        "l." | "ll." | "line" => Locator::Line,
+        "para." | "paragraph" => Locator::Paragraph,
+        _ => return (Locator::Page, locator.to_string()),
+    };
+
+    (kind, value.trim().to_string())
+}
+
+fn fill_placeholders(node: &mut Node, citations: &[String]) {
+    if let Some(html) = trusted_html_node(node)
+        && let Some(index) = html
+            .value
+            .strip_prefix(PLACEHOLDER_START)
+            .and_then(|value| value.strip_suffix(PLACEHOLDER_END))
+            .and_then(|index| index.parse::<usize>().ok())
+        && let Some(citation) = citations.get(index)
+    {
+        *node = trusted_html(citation.clone());
+        return;
+    }
+
+    for child in node.children_mut().into_iter().flatten() {
+        fill_placeholders(child, citations);
+    }
+}
+
+fn write_html(children: &ElemChildren, output: &mut String) {
+    for child in &children.0 {
+        match child {
+            ElemChild::Text(text) => {
+                write_formatted(text.formatting, &encode(&text.text), output);
+            }
+            ElemChild::Elem(element) => write_html(&element.children, output),
+            ElemChild::Markup(markup) => output.push_str(&encode(markup)),
+            ElemChild::Link { text, url } => {
+                // Links with unsafe URLs, such as `javascript:` ones, are kept as text.
+                let url = sanitize_url(url, &SAFE_HREF_PROTOCOLS);
+                if !url.is_empty() {
+                    output.push_str("<a href=\"");
+                    output.push_str(&url);
+                    output.push_str("\">");
+                }
+                write_formatted(text.formatting, &encode(&text.text), output);
+                if !url.is_empty() {
+                    output.push_str("</a>");
+                }
+            }
+            ElemChild::Transparent { .. } => {}
+        }
+    }
+}
+
+fn write_formatted(formatting: Formatting, text: &str, output: &mut String) {
+    let tags = [
+        (formatting.font_style == FontStyle::Italic, "i"),
+        (formatting.font_weight == FontWeight::Bold, "b"),
+        (formatting.text_decoration == TextDecoration::Underline, "u"),
+        (formatting.vertical_align == VerticalAlign::Sup, "sup"),
+        (formatting.vertical_align == VerticalAlign::Sub, "sub"),
+    ];
+    let small_caps = formatting.font_variant == FontVariant::SmallCaps;
+
+    if small_caps {
+        output.push_str("<span style=\"font-variant: small-caps\">");
+    }
+    for (_, tag) in tags.iter().filter(|(enabled, _)| *enabled) {
+        output.push('<');
+        output.push_str(tag);
+        output.push('>');
+    }
+    output.push_str(text);
+    for (_, tag) in tags.iter().rev().filter(|(enabled, _)| *enabled) {
+        output.push_str("</");
+        output.push_str(tag);
+        output.push('>');
+    }
+    if small_caps {
+        output.push_str("</span>");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::templates::{
+        MarkdownOptions, collect_md_headings, parse_md, parse_md_content,
+        render_md_html, test_files::TempFiles,
+    };
+    use rstest::*;
+
+    const BIBTEX: &str = "@book{knuth1984,
+        author = {Knuth, Donald E.},
+        title = {The {\\TeX}book},
+        publisher = {Addison-Wesley},
+        year = {1984},
+    }
+    @article{dijkstra1968,
+        author = {Dijkstra, Edsger W.},
+        title = {Go To Statement Considered Harmful},
+        journal = {Communications of the ACM},
+        volume = {11},
+        number = {3},
+        pages = {147--148},
+        year = {1968},
+    }";
+
+    const CSL_JSON: &str = r#"[{
+        "id": "lovelace1843",
+        "type": "article-journal",
+        "title": "Sketch of the Analytical Engine",
+        "author": [{"family": "Lovelace", "given": "Ada"}],
+        "issued": {"date-parts": [[1843]]}
+    }]"#;
+
+    fn bibliography(file: &str, content: &str, style: &str) -> Bibliography {
+        let files = TempFiles::new(&[(file, content)]);
+        Bibliography::load(
+            &files.root.join(file),
+            load_style(style).expect("Failed to load style"),
+        )
+        .expect("Failed to load bibliography")
+    }
+
+    fn render(input: &str, bibliography: &Bibliography) -> (String, String) {
+        let (html, references, _) = render_with_warnings(input, bibliography);
+
+        (html, references)
+    }
+
+    fn render_with_warnings(
+        input: &str,
+        bibliography: &Bibliography,
+    ) -> (String, String, Vec<String>) {
+        let options = MarkdownOptions::default();
+        let mut ast =
+            parse_md(input, &options).expect("Failed to parse markdown input");
+        let (references, warnings) =
+            resolve_md_citations(&mut ast, bibliography);
+        let headings = collect_md_headings(&mut ast);
+
+        (
+            render_md_html(&ast, &headings, &options),
+            references,
+            warnings,
+        )
+    }
+
+    #[test]
+    fn citations_are_formatted_in_the_style() {
+        let (html, references) = render(
+            "As shown [@dijkstra1968, p. 147; @knuth1984], `[@knuth1984]` \
+            and [@missing].",
+            &bibliography("references.bib", BIBTEX, "apa"),
+        );
+
+        assert_eq!(
+            html,
+            "<p>As shown <span class=\"citation\" data-cites=\"dijkstra1968 knuth1984\">\
+            (Dijkstra, 1968, p. 147; Knuth, 1984)</span>, <code>[@knuth1984]</code> \
+            and [@missing].</p>"
+        );
+        assert_eq!(
+            references,
+            "<div class=\"references\">\n\
+            <div id=\"ref-dijkstra1968\" class=\"reference\">Dijkstra, E. W. (1968). \
+            Go To Statement Considered Harmful. <i>Communications of the ACM</i>, \
+            <i>11</i>(3), 147–148.</div>\n\
+            <div id=\"ref-knuth1984\" class=\"reference\">Knuth, D. E. (1984). \
+            <i>The TeXbook</i>. Addison-Wesley.</div>\n</div>\n"
+        );
+    }
+
+    #[test]
+    fn unknown_keys_are_warned_about() {
+        let (html, _, warnings) = render_with_warnings(
+            "See [@knuth1984; @missing, p. 2] and [@other].",
+            &bibliography("references.bib", BIBTEX, "apa"),
+        );
+
+        assert_eq!(
+            html,
+            "<p>See [@knuth1984; @missing, p. 2] and [@other].</p>"
+        );
+        assert_eq!(
+            warnings,
+            [
+                "Citation `[@knuth1984; @missing, p. 2]` refers to keys that \
+                aren't in the bibliography: `missing`",
+                "Citation `[@other]` refers to keys that aren't in the \
+                bibliography: `other`",
+            ]
+        );
+    }
+
+    #[test]
+    fn numeric_styles_label_references() {
+        let (html, references) = render(
+            "First [@knuth1984], then [@dijkstra1968].",
+            &bibliography("references.bib", BIBTEX, "ieee"),
+        );
+
+        assert_eq!(
+            html,
+            "<p>First <span class=\"citation\" data-cites=\"knuth1984\">[1]</span>, \
+            then <span class=\"citation\" data-cites=\"dijkstra1968\">[2]</span>.</p>"
+        );
+        assert_eq!(
+            references,
+            "<div class=\"references\">\n\
+            <div id=\"ref-knuth1984\" class=\"reference\">\
+            <span class=\"reference-label\">[1]</span> D. E. Knuth, \
+            <i>The TeXbook</i>. Addison-Wesley, 1984.</div>\n\
+            <div id=\"ref-dijkstra1968\" class=\"reference\">\
+            <span class=\"reference-label\">[2]</span> E. W. Dijkstra, \
+            “Go To Statement Considered Harmful,” <i>Communications of the ACM</i>, \
+            vol. 11, no. 3, pp. 147–148, 1968.</div>\n</div>\n"
+        );
+    }
+
+    #[test]
+    fn csl_json_bibliographies_are_supported() {
+        let (html, references) = render(
+            "See [@lovelace1843].",
+            &bibliography("references.json", CSL_JSON, "apa"),
+        );
+
+        assert_eq!(
+            html,
+            "<p>See <span class=\"citation\" data-cites=\"lovelace1843\">\
+            (Lovelace, 1843)</span>.</p>"
+        );
+        assert_eq!(
+            references,
+            "<div class=\"references\">\n\
+            <div id=\"ref-lovelace1843\" class=\"reference\">Lovelace, A. (1843). \
+            <i>Sketch of the Analytical Engine</i>.</div>\n</div>\n"
+        );
+    }
+
+    #[test]
+    fn unsafe_urls_are_not_linked() {
+        let (_, references) = render(
+            "See [@safe] and [@unsafe].",
+            &bibliography(
+                "references.bib",
+                "@online{safe,
+                    title = {Safe},
+                    url = {https://example.com},
+                }
+                @online{unsafe,
+                    title = {Unsafe},
+                    url = {javascript:alert(1)},
+                }",
+                "apa",
+            ),
+        );
+
+        assert_eq!(
+            references,
+            "<div class=\"references\">\n\
+            <div id=\"ref-safe\" class=\"reference\"><i>Safe</i>. (n.d.). \
+            <a href=\"https://example.com/\">https://example.com/</a></div>\n\
+            <div id=\"ref-unsafe\" class=\"reference\"><i>Unsafe</i>. (n.d.). \
+            javascript:alert(1)</div>\n</div>\n"
+        );
+    }
+
+    #[test]
+    fn documents_without_citations_have_no_bibliography() {
+        let (html, references) = render(
+            "Some [text] and [@ nothing].",
+            &bibliography("references.bib", BIBTEX, "apa"),
+        );
+
+        assert_eq!(html, "<p>Some [text] and [@ nothing].</p>");
+        assert_eq!(references, "");
+    }
+
+    #[test]
+    fn citations_are_resolved_when_compiling_contents() {
+        let files = TempFiles::new(&[("references.bib", BIBTEX)]);
+        let options = MarkdownOptions {
+            citations: Some(Citations {
+                directory: files.root.clone(),
+                root: files.root.clone(),
+                ..Citations::default()
+            }),
+            ..MarkdownOptions::default()
+        };
+
+        let result = parse_md_content(
+            "+++\nbibliography = \"references.bib\"\n+++\nSee [@knuth1984].",
+            &options,
+        )
+        .expect("Failed to parse markdown input");
+        assert_eq!(
+            result,
+            "<p>See <span class=\"citation\" data-cites=\"knuth1984\">\
+            (Knuth, 1984)</span>.</p>"
+        );
+    }
+
+    #[rstest]
+    #[case::parent_directory("bibliography = \"../references.bib\"")]
+    #[case::absolute_path("bibliography = \"/etc/passwd\"")]
+    #[case::style("bibliography = \"references.bib\"\ncsl = \"../style.csl\"")]
+    fn frontmatter_paths_outside_of_the_root_are_errors(
+        #[case] frontmatter: &str,
+    ) {
+        let files = TempFiles::new(&[
+            ("references.bib", BIBTEX),
+            ("style.csl", ""),
+            ("docs/references.bib", BIBTEX),
+        ]);
+        let citations = Citations {
+            directory: files.root.join("docs"),
+            root: files.root.join("docs"),
+            ..Citations::default()
+        };
+        let frontmatter =
+            toml::from_str(frontmatter).expect("Invalid frontmatter");
+
+        assert!(citations.load(&frontmatter).is_err());
+    }
+
+    #[test]
+    fn unknown_styles_are_errors() {
+        assert!(load_style("nonexistent-style").is_err());
+    }
+}
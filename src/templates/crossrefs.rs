@@ -0,0 +1,397 @@
+use super::{
+    Figure, MarkdownHeading, headings::take_trailing_id, html_element,
+    trusted_html,
+};
+use markdown::mdast::{Link, Node, Paragraph, Text};
+use std::collections::HashMap;
+
+const REFERENCE_PREFIXES: [&str; 3] = ["sec:", "fig:", "tbl:"];
+
+/// Settings for how sections and tables are labelled when numbering them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CrossReferences {
+    /// Label of sections, such as `Section` in `Section 2.1`.
+    pub section_label: String,
+    /// Label of tables, such as `Table` in `Table 1`.
+    pub table_label: String,
+}
+
+impl Default for CrossReferences {
+    fn default() -> Self {
+        Self {
+            section_label: "Section".to_string(),
+            table_label: "Table".to_string(),
+        }
+    }
+}
+
+/// Numbers the sections, figures and captioned tables of a Markdown ast, and fills the references
+/// to them with their label and number, as in `Section 2.1`, `Figure 3` or `Table 1`.
+///
+/// Headings start with their number, as in `<span class="section-number">2.1</span> Design`.
+/// Figures are numbered by [`transform_md_figures`](super::transform_md_figures), whose captions
+/// should be numbered too.
+///
+/// `@sec:id`, `@fig:id` and `@tbl:id` become links to the heading, figure or table with that id,
+/// and empty links such as `[](#id)` get its number as their text. References to ids that don't
+/// exist are left untouched.
+///
+/// A table is captioned by a paragraph right after it starting with `Table:` or `:`, which can
+/// end with a `{#tbl:id}` attribute. It's then put in a `<figure>` with its numbered caption.
+pub fn resolve_md_cross_references(
+    root: &mut Node,
+    headings: &[MarkdownHeading],
+    figures: &[Figure],
+    figure_label: &str,
+    cross_references: &CrossReferences,
+) {
+    let mut labels =
+        number_sections(root, headings, &cross_references.section_label);
+    for figure in figures {
+        labels.insert(
+            figure.id.clone(),
+            format!("{figure_label} {}", figure.number),
+        );
+    }
+    let mut tables = 0;
+    caption_tables(
+        root,
+        &cross_references.table_label,
+        &mut tables,
+        &mut labels,
+    );
+
+    fill_references(root, &labels, false);
+}
+
+fn number_sections(
+    root: &mut Node,
+    headings: &[MarkdownHeading],
+    label: &str,
+) -> HashMap<String, String> {
+    let top_level = headings
+        .iter()
+        .map(|heading| heading.level)
+        .min()
+        .unwrap_or(1);
+
+    let mut counters = [0_usize; 6];
+    let mut numbers = HashMap::new();
+    let mut labels = HashMap::new();
+    for heading in headings {
+        let depth = usize::from(heading.level - top_level);
+        counters[depth] += 1;
+        counters[depth + 1..].fill(0);
+        let number: Vec<_> =
+            counters[..=depth].iter().map(ToString::to_string).collect();
+        let number = number.join(".");
+
+        labels.insert(heading.id.clone(), format!("{label} {number}"));
+        if let Some(offset) = heading.offset {
+            numbers.insert(offset, number);
+        }
+    }
+    number_headings(root, &numbers);
+
+    labels
+}
+
+/// Prefixes the headings of a Markdown ast with their number, found by their offset.
+fn number_headings(node: &mut Node, numbers: &HashMap<usize, String>) {
+    if let Node::Heading(heading) = node {
+        if let Some(number) = heading
+            .position
+            .as_ref()
+            .and_then(|position| numbers.get(&position.start.offset))
+        {
+            heading.children.insert(
+                0,
+                trusted_html(format!(
+                    "<span class=\"section-number\">{number}</span> "
+                )),
+            );
+        }
+        return;
+    }
+
+    for child in node.children_mut().into_iter().flatten() {
+        number_headings(child, numbers);
+    }
+}
+
+fn caption_tables(
+    node: &mut Node,
+    table_label: &str,
+    tables: &mut usize,
+    labels: &mut HashMap<String, String>,
+) {
+    let Some(children) = node.children_mut() else {
+        return;
+    };
+
+    let mut index = 0;
+    while index < children.len() {
+        if matches!(children[index], Node::Table(_))
+            && let Some(Node::Paragraph(paragraph)) =
+                children.get_mut(index + 1)
+            && let Some((caption, id)) = table_caption(paragraph)
+        {
+            *tables += 1;
+            let id = id.unwrap_or_else(|| format!("table-{tables}"));
+            let label = format!("{table_label} {tables}");
+
+            let mut caption_children = vec![Node::Text(Text {
+                value: format!("{label}: "),
+                position: None,
+            })];
+            caption_children.extend(caption);
+            labels.insert(id.clone(), label);
+
+            let table = children.remove(index);
+            children[index] = html_element(
+                "figure",
+                &[("id", &id), ("class", "table")],
+                vec![table, html_element("figcaption", &[], caption_children)],
+            );
+        } else {
+            caption_tables(&mut children[index], table_label, tables, labels);
+        }
+        index += 1;
+    }
+}
+
+/// Returns the contents of a `Table: caption {#tbl:id}` paragraph and its id, if any.
+fn table_caption(
+    paragraph: &mut Paragraph,
+) -> Option<(Vec<Node>, Option<String>)> {
+    let Some(Node::Text(first)) = paragraph.children.first_mut() else {
+        return None;
+    };
+    let rest = first
+        .value
+        .strip_prefix("Table:")
+        .or_else(|| first.value.strip_prefix(':'))?;
+    first.value = rest.trim_start().to_string();
+
+    let mut caption = std::mem::take(&mut paragraph.children);
+    let id = take_trailing_id(&mut caption);
+    caption.retain(
+        |node| !matches!(node, Node::Text(text) if text.value.is_empty()),
+    );
+
+    Some((caption, id))
+}
+
+fn fill_references(
+    node: &mut Node,
+    labels: &HashMap<String, String>,
+    in_link: bool,
+) {
+    if let Node::Link(link) = node
+        && link.children.is_empty()
+        && let Some(label) =
+            link.url.strip_prefix('#').and_then(|id| labels.get(id))
+    {
+        link.children.push(Node::Text(Text {
+            value: label.clone(),
+            position: None,
+        }));
+        return;
+    }
+
+    let in_link = in_link || matches!(node, Node::Link(_));
+    let Some(children) = node.children_mut() else {
+        return;
+    };
+
+    let mut index = 0;
+    while index < children.len() {
+        if !in_link
+            && let Node::Text(text) = &children[index]
+            && let Some(nodes) = split_references(&text.value, labels)
+        {
+            let count = nodes.len();
+            children.splice(index..=index, nodes);
+            index += count;
+        } else {
+            fill_references(&mut children[index], labels, in_link);
+            index += 1;
+        }
+    }
+}
+
+/// Splits a text into text and link nodes for each of its references, or returns `None` if it
+/// holds none.
+fn split_references(
+    text: &str,
+    labels: &HashMap<String, String>,
+) -> Option<Vec<Node>> {
+    let mut nodes = Vec::new();
+    let mut rest = text;
+    let mut start = 0;
+    while let Some(at) = rest[start..].find('@').map(|at| start + at) {
+        start = at + 1;
+        if rest[..at].chars().last().is_some_and(char::is_alphanumeric) {
+            continue;
+        }
+
+        let reference = &rest[at + 1..];
+        if !REFERENCE_PREFIXES
+            .iter()
+            .any(|prefix| reference.starts_with(prefix))
+        {
+            continue;
+        }
+        let end = reference
+            .find(|c: char| !(c.is_alphanumeric() || "-_:.".contains(c)))
+            .unwrap_or(reference.len());
+        let id = reference[..end].trim_end_matches(['.', ':']);
+        let Some(label) = labels.get(id) else {
+            continue;
+        };
+
+        if at > 0 {
+            nodes.push(Node::Text(Text {
+                value: rest[..at].to_string(),
+                position: None,
+            }));
+        }
+        nodes.push(Node::Link(Link {
+            children: vec![Node::Text(Text {
+                value: label.clone(),
+                position: None,
+            })],
+            position: None,
+            url: format!("#{id}"),
+            title: None,
+        }));
+        rest = &reference[id.len()..];
+        start = 0;
+    }
+
+    if nodes.is_empty() {
+        return None;
+    }
+    if !rest.is_empty() {
+        nodes.push(Node::Text(Text {
+            value: rest.to_string(),
+            position: None,
+        }));
+    }
+
+    Some(nodes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::templates::{Figures, MarkdownOptions, parse_md_content};
+    use rstest::*;
+
+    fn render_with(input: &str, cross_references: CrossReferences) -> String {
+        let options = MarkdownOptions {
+            figures: Some(Figures::default()),
+            cross_references: Some(cross_references),
+            ..MarkdownOptions::default()
+        };
+
+        parse_md_content(input, &options)
+            .expect("Failed to parse markdown input")
+    }
+
+    fn render(input: &str) -> String {
+        render_with(input, CrossReferences::default())
+    }
+
+    #[test]
+    fn sections_are_numbered_by_level() {
+        assert_eq!(
+            render(
+                "## Intro {#sec:intro}\n\n### Scope\n\n### Goals {#sec:goals}\n\n\
+                ## Design\n\nSee @sec:goals and [](#sec:intro)."
+            ),
+            "<h2 id=\"sec:intro\"><span class=\"section-number\">1</span> Intro</h2>\n<h3 id=\"scope\"><span class=\"section-number\">1.1</span> Scope</h3>\n\
+            <h3 id=\"sec:goals\"><span class=\"section-number\">1.2</span> Goals</h3>\n<h2 id=\"design\"><span class=\"section-number\">2</span> Design</h2>\n\
+            <p>See <a href=\"#sec:goals\">Section 1.2</a> and \
+            <a href=\"#sec:intro\">Section 1</a>.</p>"
+        );
+    }
+
+    #[test]
+    fn figures_are_referenced() {
+        assert_eq!(
+            render(
+                "![Boxes](arch.png \"Architecture\") {#fig:arch}\n\n\
+                As @fig:arch shows, [it](#fig:arch) works."
+            ),
+            "<figure id=\"fig:arch\">\n<img src=\"arch.png\" alt=\"Boxes\" />\n\
+            <figcaption>Figure 1: Architecture</figcaption>\n</figure>\n\
+            <p>As <a href=\"#fig:arch\">Figure 1</a> shows, \
+            <a href=\"#fig:arch\">it</a> works.</p>"
+        );
+    }
+
+    #[test]
+    fn tables_are_captioned_and_referenced() {
+        assert_eq!(
+            render(
+                "| a |\n| - |\n| 1 |\n\nTable: Some *values* {#tbl:values}\n\n\
+                | b |\n| - |\n\n: Others\n\nSee @tbl:values."
+            ),
+            "<figure id=\"tbl:values\" class=\"table\">\n<table>\n<thead>\n\
+            <tr>\n<th>a</th>\n</tr>\n</thead>\n<tbody>\n<tr>\n<td>1</td>\n</tr>\n\
+            </tbody>\n</table>\n<figcaption>Table 1: Some <em>values</em>\
+            </figcaption>\n</figure>\n<figure id=\"table-2\" class=\"table\">\n\
+            <table>\n<thead>\n<tr>\n<th>b</th>\n</tr>\n</thead>\n</table>\n\
+            <figcaption>Table 2: Others</figcaption>\n</figure>\n\
+            <p>See <a href=\"#tbl:values\">Table 1</a>.</p>"
+        );
+    }
+
+    #[test]
+    fn labels_can_be_changed() {
+        assert_eq!(
+            render_with(
+                "# Intro {#sec:intro}\n\n| a |\n| - |\n\n: Values {#tbl:values}\n\n\
+                See @sec:intro and @tbl:values.",
+                CrossReferences {
+                    section_label: "Abschnitt".to_string(),
+                    table_label: "Tabelle".to_string(),
+                },
+            ),
+            "<h1 id=\"sec:intro\"><span class=\"section-number\">1</span> Intro</h1>\n<figure id=\"tbl:values\" class=\"table\">\n\
+            <table>\n<thead>\n<tr>\n<th>a</th>\n</tr>\n</thead>\n</table>\n\
+            <figcaption>Tabelle 1: Values</figcaption>\n</figure>\n\
+            <p>See <a href=\"#sec:intro\">Abschnitt 1</a> and \
+            <a href=\"#tbl:values\">Tabelle 1</a>.</p>"
+        );
+    }
+
+    #[rstest]
+    #[case::unknown_id("See @sec:missing.", "<p>See @sec:missing.</p>")]
+    #[case::email("Mail me@sec:intro.", "<p>Mail me@sec:intro.</p>")]
+    #[case::other_prefix("Hi @someone", "<p>Hi @someone</p>")]
+    #[case::code("`@sec:intro`", "<p><code>@sec:intro</code></p>")]
+    fn other_text_is_kept(#[case] input: &str, #[case] expected: &str) {
+        let input = format!("# Intro {{#sec:intro}}\n\n{input}");
+
+        assert_eq!(
+            render(&input),
+            format!(
+                "<h1 id=\"sec:intro\"><span class=\"section-number\">1</span> Intro</h1>\n{expected}"
+            )
+        );
+    }
+
+    #[test]
+    fn references_are_disabled_by_default() {
+        assert_eq!(
+            parse_md_content(
+                "# A {#sec:a}\n\n@sec:a",
+                &MarkdownOptions::default()
+            )
+            .expect("Failed to parse markdown input"),
+            "<h1 id=\"sec:a\">A</h1>\n<p>@sec:a</p>"
+        );
+    }
+}
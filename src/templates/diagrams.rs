@@ -0,0 +1,110 @@
+use std::sync::LazyLock;
+use svgbob::Settings;
+
+/// Ids of the markers that Svgbob defines in every diagram, such as arrowheads.
+const MARKER_IDS: [&str; 5] = [
+    "arrow",
+    "diamond",
+    "circle",
+    "open_circle",
+    "big_open_circle",
+];
+/// Prefix given to the ids of [`MARKER_IDS`], so they don't clash with the page's own ids.
+const MARKER_ID_PREFIX: &str = "svgbob-";
+
+static SETTINGS: LazyLock<Settings> = LazyLock::new(|| Settings {
+    font_family: "monospace".to_string(),
+    fill_color: "currentColor".to_string(),
+    background: "transparent".to_string(),
+    stroke_color: "currentColor".to_string(),
+    include_backdrop: false,
+    ..Settings::default()
+});
+
+/// Converts an ASCII diagram into an inline SVG image with
+/// [Svgbob](https://github.com/ivanceras/svgbob).
+///
+/// Lines, arrows and shapes use `currentColor`, so the diagram follows the color of the page
+/// around it.
+///
+/// Returns `None` if the diagram is empty.
+#[must_use]
+pub fn render_diagram(ascii: &str) -> Option<String> {
+    if ascii.trim().is_empty() {
+        return None;
+    }
+
+    let mut svg = svgbob::to_svg_with_settings(ascii, &SETTINGS);
+    for id in MARKER_IDS {
+        svg = svg
+            .replace(
+                &format!("id=\"{id}\""),
+                &format!("id=\"{MARKER_ID_PREFIX}{id}\""),
+            )
+            .replace(
+                &format!("url(#{id})"),
+                &format!("url(#{MARKER_ID_PREFIX}{id})"),
+            );
+    }
+
+    Some(svg.trim_end().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::templates::{
+        MarkdownOptions, collect_md_headings, parse_md, render_md_html,
+    };
+    use rstest::*;
+
+    #[rstest]
+    #[case::lines(
+        "+--+",
+        "<line x1=\"4\" y1=\"8\" x2=\"28\" y2=\"8\" class=\"solid\">"
+    )]
+    #[case::arrows(
+        "-->",
+        "<polygon points=\"16,4 24,8 16,12\" class=\"filled\">"
+    )]
+    #[case::rounded_corners(
+        ".-.\n' '",
+        "<path d=\"M 8,8 A 4,4 0,0,0 4,12\" class=\"nofill\">"
+    )]
+    #[case::escaped_text("<a & b>", "<text x=\"26\" y=\"12\" >&amp;</text>")]
+    fn shapes_are_drawn(#[case] input: &str, #[case] expected: &str) {
+        let svg = render_diagram(input).expect("Failed to render diagram");
+
+        assert!(svg.starts_with("<svg "), "{svg}");
+        assert!(svg.contains(expected), "{svg}");
+    }
+
+    #[test]
+    fn marker_ids_are_prefixed() {
+        let svg = render_diagram("*--").expect("Failed to render diagram");
+
+        assert!(svg.contains("<marker id=\"svgbob-arrow\""), "{svg}");
+        assert!(svg.contains("marker-end: url(#svgbob-circle);"), "{svg}");
+        assert!(!svg.contains("id=\"arrow\""), "{svg}");
+    }
+
+    #[test]
+    fn empty_diagrams_are_not_rendered() {
+        assert_eq!(render_diagram(" \n\n"), None);
+    }
+
+    #[rstest]
+    #[case::enabled(true, "<svg ")]
+    #[case::disabled(false, "<pre><code class=\"language-bob\">")]
+    fn diagram_blocks_become_svg(#[case] diagrams: bool, #[case] start: &str) {
+        let options = MarkdownOptions {
+            diagrams,
+            ..MarkdownOptions::default()
+        };
+        let mut ast = parse_md("```bob\n+--+\n```", &options)
+            .expect("Failed to parse markdown input");
+        let headings = collect_md_headings(&mut ast);
+
+        assert!(render_md_html(&ast, &headings, &options).starts_with(start));
+    }
+}
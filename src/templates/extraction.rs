@@ -1,3 +1,4 @@
+use super::is_flow_content;
 use color_eyre::eyre::eyre;
 use markdown::mdast::Node;
 
@@ -22,6 +23,124 @@ pub fn extract_md_frontmatter(root: &Node) -> color_eyre::Result<String> {
     }
 }
 
+/// Given the source of a Markdown file, returns the source of its contents without the
+/// frontmatter.
+#[must_use]
+pub fn extract_md_body(input: &str) -> &str {
+    input[frontmatter_length(input)..].trim_start_matches(['\r', '\n'])
+}
+
+/// Given the root node of a Markdown ast, returns a plain text rendering of its contents, with its
+/// blocks separated by blank lines.
+#[must_use]
+pub fn extract_md_text(root: &Node) -> String {
+    let mut blocks = Vec::new();
+    collect_text_blocks(root, &mut blocks, true);
+
+    blocks.join("\n\n")
+}
+
+/// Given the root node of a Markdown ast, returns a plain text rendering of its prose, like
+/// [`extract_md_text`] but without its code and math.
+#[must_use]
+pub fn extract_md_prose(root: &Node) -> String {
+    let mut blocks = Vec::new();
+    collect_text_blocks(root, &mut blocks, false);
+
+    blocks.join("\n\n")
+}
+
+fn collect_text_blocks(node: &Node, blocks: &mut Vec<String>, code: bool) {
+    let block = match node {
+        Node::Root(_)
+        | Node::Blockquote(_)
+        | Node::List(_)
+        | Node::ListItem(_)
+        | Node::FootnoteDefinition(_) => {
+            for child in node.children().into_iter().flatten() {
+                collect_text_blocks(child, blocks, code);
+            }
+            return;
+        }
+        Node::MdxJsxFlowElement(element)
+            if element.children.iter().any(is_flow_content) =>
+        {
+            for child in &element.children {
+                collect_text_blocks(child, blocks, code);
+            }
+            return;
+        }
+        Node::Table(table) => table
+            .children
+            .iter()
+            .map(|row| {
+                row.children()
+                    .into_iter()
+                    .flatten()
+                    .map(|cell| node_text(cell, code))
+                    .collect::<Vec<_>>()
+                    .join("\t")
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+        Node::Code(block) if code => block.value.clone(),
+        Node::Math(math) if code => math.value.clone(),
+        Node::Heading(_) | Node::Paragraph(_) | Node::MdxJsxFlowElement(_) => {
+            node_text(node, code)
+        }
+        _ => return,
+    };
+
+    if !block.trim().is_empty() {
+        blocks.push(block);
+    }
+}
+
+/// Returns the length of the frontmatter at the start of a Markdown `&str`, including its
+/// delimiters, or 0 if there's none.
+pub(super) fn frontmatter_length(input: &str) -> usize {
+    let Some(delimiter) = input
+        .split_inclusive('\n')
+        .next()
+        .map(str::trim_end)
+        .filter(|line| ["+++", "---"].contains(line))
+    else {
+        return 0;
+    };
+
+    let mut length = 0;
+    for (index, line) in input.split_inclusive('\n').enumerate() {
+        length += line.len();
+        if index > 0 && line.trim_end() == delimiter {
+            return length;
+        }
+    }
+
+    0
+}
+
+pub(super) fn inline_text(node: &Node) -> String {
+    node_text(node, true)
+}
+
+fn node_text(node: &Node, code: bool) -> String {
+    match node {
+        Node::Text(text) => text.value.clone(),
+        Node::InlineCode(inline) if code => inline.value.clone(),
+        Node::InlineMath(math) if code => math.value.clone(),
+        Node::InlineCode(_) | Node::InlineMath(_) => String::new(),
+        Node::Image(image) => image.alt.clone(),
+        Node::ImageReference(image) => image.alt.clone(),
+        Node::Break(_) => "\n".to_string(),
+        _ => node
+            .children()
+            .into_iter()
+            .flatten()
+            .map(|child| node_text(child, code))
+            .collect(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -86,6 +205,45 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[rstest]
+    fn extract_body_skips_the_frontmatter(md_test: &str) {
+        let result = extract_md_body(md_test);
+        assert!(result.starts_with("# Lorem ipsum dolor sit amet\n\nLorem"));
+    }
+
+    #[rstest]
+    #[case::no_frontmatter("# This is a title\n\nThis is a paragraph")]
+    #[case::unclosed_frontmatter("+++\ntitle = \"A\"\n\nThis is a paragraph")]
+    fn extract_body_keeps_inputs_without_frontmatter(#[case] input: &str) {
+        assert_eq!(extract_md_body(input), input);
+    }
+
+    #[test]
+    fn extract_text_removes_markup() {
+        let input = "+++\n+++\n\n# A *title*\n\nSome **bold** `code`.\n\n\
+            - One\n- [Two](https://example.com)\n\n```rust\nlet x = 1;\n```";
+        let tree = markdown::to_mdast(input, &default_md_parse_options(false))
+            .expect("This should not fail");
+
+        assert_eq!(
+            extract_md_text(&tree),
+            "A title\n\nSome bold code.\n\nOne\n\nTwo\n\nlet x = 1;"
+        );
+    }
+
+    #[test]
+    fn extract_prose_skips_code_and_math() {
+        let input =
+            "Some `code` and $x$.\n\n```rust\nlet x = 1;\n```\n\n$$\nx^2\n$$";
+        let mut options = default_md_parse_options(false);
+        options.constructs.math_flow = true;
+        options.constructs.math_text = true;
+        let tree =
+            markdown::to_mdast(input, &options).expect("This should not fail");
+
+        assert_eq!(extract_md_prose(&tree), "Some  and .");
+    }
+
     #[rstest]
     fn extract_frontmatter_errors_on_frontmatter_of_different_type(
         yaml_test: &str,
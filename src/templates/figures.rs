@@ -0,0 +1,257 @@
+use super::{
+    MarkdownHeading,
+    headings::{expression_id, take_id_attribute, unique_id},
+    html_element,
+};
+use markdown::mdast::{Image, Node, Text};
+use serde::Serialize;
+use std::collections::HashSet;
+
+/// Settings for how images alone in their paragraph are turned into figures.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Figures {
+    /// Whether to prefix captions with the label and number of their figure.
+    pub numbered: bool,
+    /// Label of numbered captions, such as `Figure` in `Figure 1: Caption`.
+    pub label: String,
+}
+
+impl Default for Figures {
+    fn default() -> Self {
+        Self {
+            numbered: false,
+            label: "Figure".to_string(),
+        }
+    }
+}
+
+/// A figure of a Markdown document.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Figure {
+    /// Position of the figure in the document, starting at 1.
+    pub number: usize,
+    /// Id of the figure's `<figure>` element.
+    pub id: String,
+    pub caption: String,
+    pub src: String,
+    pub alt: String,
+}
+
+/// Replaces the paragraphs of a Markdown ast holding only an image with a title, as in
+/// `![alt](image.png "Caption")`, with a `<figure>` of the image captioned by its title.
+///
+/// A `{#id}` attribute right after the image sets the id of the figure. Ids are unique, with a
+/// numeric suffix added when a heading in `headings` or an earlier figure already uses them, and
+/// a warning for each explicit id that's used more than once.
+///
+/// Returns the figures in document order and the warnings.
+pub fn transform_md_figures(
+    root: &mut Node,
+    figures: &Figures,
+    headings: &[MarkdownHeading],
+) -> (Vec<Figure>, Vec<String>) {
+    let mut list = Vec::new();
+    let mut used_ids =
+        headings.iter().map(|heading| heading.id.clone()).collect();
+    let mut warnings = Vec::new();
+    transform_figures(root, figures, &mut list, &mut used_ids, &mut warnings);
+
+    (list, warnings)
+}
+
+fn transform_figures(
+    node: &mut Node,
+    figures: &Figures,
+    list: &mut Vec<Figure>,
+    used_ids: &mut HashSet<String>,
+    warnings: &mut Vec<String>,
+) {
+    if let Some((image, id)) = figure_image(node) {
+        let number = list.len() + 1;
+        let unique = unique_id(
+            id.as_deref().unwrap_or(&format!("figure-{number}")),
+            used_ids,
+        );
+        if let Some(id) = id.filter(|id| *id != unique) {
+            warnings.push(format!(
+                "Figure id `{id}` is already used, using `{unique}` instead"
+            ));
+        }
+
+        let figure = Figure {
+            number,
+            id: unique,
+            caption: image.title.clone().unwrap_or_default(),
+            src: image.url.clone(),
+            alt: image.alt.clone(),
+        };
+        let caption = if figures.numbered {
+            format!("{} {}: {}", figures.label, figure.number, figure.caption)
+        } else {
+            figure.caption.clone()
+        };
+
+        *node = html_element(
+            "figure",
+            &[("id", &figure.id)],
+            vec![
+                Node::Image(Image {
+                    title: None,
+                    ..image
+                }),
+                html_element(
+                    "figcaption",
+                    &[],
+                    vec![Node::Text(Text {
+                        value: caption,
+                        position: None,
+                    })],
+                ),
+            ],
+        );
+        list.push(figure);
+        return;
+    }
+
+    for child in node.children_mut().into_iter().flatten() {
+        transform_figures(child, figures, list, used_ids, warnings);
+    }
+}
+
+/// Returns the image of a paragraph that holds only an image with a title, and the id set by a
+/// `{#id}` attribute after it, if any.
+fn figure_image(node: &Node) -> Option<(Image, Option<String>)> {
+    let Node::Paragraph(paragraph) = node else {
+        return None;
+    };
+
+    let mut children = paragraph.children.iter().filter(
+        |child| !matches!(child, Node::Text(text) if text.value.trim().is_empty()),
+    );
+    let Some(Node::Image(image)) = children.next() else {
+        return None;
+    };
+    let id = match children.next() {
+        Some(Node::Text(text)) => {
+            let mut value = text.value.clone();
+            let id = take_id_attribute(&mut value)?;
+            value.trim().is_empty().then_some(id)
+        }
+        Some(Node::MdxTextExpression(expression)) => {
+            Some(expression_id(&expression.value)?)
+        }
+        Some(_) => return None,
+        None => None,
+    };
+
+    (children.next().is_none() && image.title.is_some())
+        .then(|| (image.clone(), id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::templates::{
+        MarkdownOptions, collect_md_headings, parse_md, render_md_html,
+    };
+    use rstest::*;
+
+    fn render(input: &str, figures: &Figures) -> (String, Vec<Figure>) {
+        let options = MarkdownOptions::default();
+        let mut ast =
+            parse_md(input, &options).expect("Failed to parse markdown input");
+        let headings = collect_md_headings(&mut ast);
+        let (list, _) = transform_md_figures(&mut ast, figures, &headings);
+
+        (render_md_html(&ast, &headings, &options), list)
+    }
+
+    #[rstest]
+    #[case::figure(
+        "![A chart](chart.png \"Sales *by* year\")",
+        "<figure id=\"figure-1\">\n<img src=\"chart.png\" alt=\"A chart\" />\n\
+        <figcaption>Sales *by* year</figcaption>\n</figure>"
+    )]
+    #[case::with_id(
+        "![A chart](chart.png \"Sales\") {#fig:sales}",
+        "<figure id=\"fig:sales\">\n<img src=\"chart.png\" alt=\"A chart\" />\n\
+        <figcaption>Sales</figcaption>\n</figure>"
+    )]
+    #[case::without_title(
+        "![A chart](chart.png)",
+        "<p><img src=\"chart.png\" alt=\"A chart\" /></p>"
+    )]
+    #[case::with_text(
+        "See ![A chart](chart.png \"Sales\")",
+        "<p>See <img src=\"chart.png\" alt=\"A chart\" title=\"Sales\" /></p>"
+    )]
+    fn images_with_titles_become_figures(
+        #[case] input: &str,
+        #[case] expected: &str,
+    ) {
+        assert_eq!(render(input, &Figures::default()).0, expected);
+    }
+
+    #[test]
+    fn figures_are_numbered_and_listed() {
+        let figures = Figures {
+            numbered: true,
+            label: "Fig.".to_string(),
+        };
+        let (html, list) = render(
+            "![One](1.png \"First\")\n\n> ![Two](2.png \"Second\")",
+            &figures,
+        );
+
+        assert_eq!(
+            html,
+            "<figure id=\"figure-1\">\n<img src=\"1.png\" alt=\"One\" />\n\
+            <figcaption>Fig. 1: First</figcaption>\n</figure>\n<blockquote>\n\
+            <figure id=\"figure-2\">\n<img src=\"2.png\" alt=\"Two\" />\n\
+            <figcaption>Fig. 2: Second</figcaption>\n</figure>\n</blockquote>"
+        );
+        assert_eq!(
+            list,
+            vec![
+                Figure {
+                    number: 1,
+                    id: "figure-1".to_string(),
+                    caption: "First".to_string(),
+                    src: "1.png".to_string(),
+                    alt: "One".to_string(),
+                },
+                Figure {
+                    number: 2,
+                    id: "figure-2".to_string(),
+                    caption: "Second".to_string(),
+                    src: "2.png".to_string(),
+                    alt: "Two".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn figure_ids_are_unique() {
+        let mut ast = parse_md(
+            "# Sales {#fig:sales}\n\n![One](1.png \"First\") {#fig:sales}\n\n\
+            ![Two](2.png \"Second\") {#fig:sales}\n\n# Figure 3\n\n![Three](3.png \"Third\")",
+            &MarkdownOptions::default(),
+        )
+        .expect("Failed to parse markdown input");
+        let headings = collect_md_headings(&mut ast);
+
+        let (list, warnings) =
+            transform_md_figures(&mut ast, &Figures::default(), &headings);
+        let ids: Vec<_> =
+            list.iter().map(|figure| figure.id.as_str()).collect();
+        assert_eq!(ids, ["fig:sales-1", "fig:sales-2", "figure-3-1"]);
+        assert_eq!(
+            warnings,
+            [
+                "Figure id `fig:sales` is already used, using `fig:sales-1` instead",
+                "Figure id `fig:sales` is already used, using `fig:sales-2` instead",
+            ]
+        );
+    }
+}
@@ -0,0 +1,335 @@
+use super::extraction::inline_text;
+use markdown::mdast::Node;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::iter::Peekable;
+
+/// A heading of a Markdown document, with its anchor id.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MarkdownHeading {
+    pub level: u8,
+    pub text: String,
+    pub id: String,
+    /// Offset of the heading in the Markdown source, if known.
+    pub offset: Option<usize>,
+}
+
+/// An entry of a table of contents, nesting the headings below it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TocEntry {
+    pub level: u8,
+    pub text: String,
+    pub id: String,
+    pub children: Vec<Self>,
+}
+
+/// Turns a heading's text into an anchor id, lowercasing it, replacing whitespace with hyphens
+/// and removing punctuation. Text without any letters or digits, such as an emoji, becomes
+/// `section`.
+#[must_use]
+pub fn slugify(text: &str) -> String {
+    let text: String = text
+        .chars()
+        .filter(|c| {
+            c.is_alphanumeric() || c.is_whitespace() || *c == '-' || *c == '_'
+        })
+        .collect();
+    let slug: String = text
+        .trim()
+        .chars()
+        .map(|c| if c.is_whitespace() { '-' } else { c })
+        .flat_map(char::to_lowercase)
+        .collect();
+
+    if slug.chars().any(char::is_alphanumeric) {
+        slug
+    } else {
+        "section".to_string()
+    }
+}
+
+/// Given the root node of a Markdown ast, returns its headings in document order.
+///
+/// A heading ending in an explicit `{#id}` attribute uses that id, and the attribute is removed
+/// from the ast. Every other heading gets an id based on its text. Ids are unique, with a numeric
+/// suffix added when an earlier heading, or one with that explicit id, already uses them.
+pub fn collect_md_headings(root: &mut Node) -> Vec<MarkdownHeading> {
+    let mut headings = Vec::new();
+    collect_headings(root, &mut headings);
+
+    let mut used_ids = HashSet::new();
+    for heading in &mut headings {
+        if !heading.id.is_empty() {
+            heading.id = unique_id(&heading.id, &mut used_ids);
+        }
+    }
+    for heading in &mut headings {
+        if heading.id.is_empty() {
+            heading.id = unique_id(&slugify(&heading.text), &mut used_ids);
+        }
+    }
+
+    headings
+}
+
+fn collect_headings(node: &mut Node, headings: &mut Vec<MarkdownHeading>) {
+    if let Node::Heading(heading) = node {
+        let id = take_trailing_id(&mut heading.children);
+
+        let offset = heading.position.as_ref().map(|p| p.start.offset);
+        let level = heading.depth;
+        headings.push(MarkdownHeading {
+            level,
+            text: inline_text(node),
+            id: id.unwrap_or_default(),
+            offset,
+        });
+        return;
+    }
+
+    for child in node.children_mut().into_iter().flatten() {
+        collect_headings(child, headings);
+    }
+}
+
+/// Returns the heading nodes of a Markdown ast, in the order of [`collect_md_headings`].
+pub(super) fn md_heading_nodes(root: &Node) -> Vec<&Node> {
+    if matches!(root, Node::Heading(_)) {
+        return vec![root];
+    }
+
+    root.children()
+        .into_iter()
+        .flatten()
+        .flat_map(md_heading_nodes)
+        .collect()
+}
+
+/// Removes a trailing `{#id}` attribute from `text`, returning its id.
+///
+/// Ids can only contain alphanumerics, `-`, `_`, `:` and `.`.
+pub(super) fn take_id_attribute(text: &mut String) -> Option<String> {
+    let trimmed = text.trim_end();
+    let start = trimmed.strip_suffix('}')?.rfind("{#")?;
+    let id = &trimmed[start + 2..trimmed.len() - 1];
+    if id.is_empty() || !id.chars().all(is_id_char) {
+        return None;
+    }
+
+    let id = id.to_string();
+    text.truncate(trimmed[..start].trim_end().len());
+    Some(id)
+}
+
+/// Removes a trailing `{#id}` attribute from inline `children`, returning its id. With MDX
+/// expressions enabled, the attribute is parsed as an expression instead of as text.
+pub(super) fn take_trailing_id(children: &mut Vec<Node>) -> Option<String> {
+    let id = match children.last_mut()? {
+        Node::Text(text) => take_id_attribute(&mut text.value)?,
+        Node::MdxTextExpression(expression) => {
+            let id = expression_id(&expression.value)?;
+            children.pop();
+            if let Some(Node::Text(text)) = children.last_mut() {
+                text.value.truncate(text.value.trim_end().len());
+            }
+            id
+        }
+        _ => return None,
+    };
+    if let Some(Node::Text(text)) = children.last()
+        && text.value.is_empty()
+    {
+        children.pop();
+    }
+
+    Some(id)
+}
+
+/// Returns the id of an MDX expression that holds a `{#id}` attribute.
+pub(super) fn expression_id(value: &str) -> Option<String> {
+    let id = value.trim().strip_prefix('#')?;
+    (!id.is_empty() && id.chars().all(is_id_char)).then(|| id.to_string())
+}
+
+fn is_id_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '-' | '_' | ':' | '.')
+}
+
+pub(super) fn unique_id(slug: &str, used_ids: &mut HashSet<String>) -> String {
+    let mut id = slug.to_string();
+    let mut suffix = 0;
+    while !used_ids.insert(id.clone()) {
+        suffix += 1;
+        id = format!("{slug}-{suffix}");
+    }
+
+    id
+}
+
+/// Moves every heading of a Markdown ast `offset` levels down, or up if it's negative, keeping
+/// their levels between 1 and 6.
+pub fn shift_md_headings(root: &mut Node, offset: i8) {
+    if let Node::Heading(heading) = root {
+        heading.depth = heading.depth.saturating_add_signed(offset).clamp(1, 6);
+    }
+
+    for child in root.children_mut().into_iter().flatten() {
+        shift_md_headings(child, offset);
+    }
+}
+
+/// Builds a table of contents from a document's headings.
+///
+/// Each heading is nested under the closest previous heading with a lower level.
+#[must_use]
+pub fn build_md_toc(headings: &[MarkdownHeading]) -> Vec<TocEntry> {
+    nest_headings(&mut headings.iter().peekable(), 0)
+}
+
+fn nest_headings<'a>(
+    headings: &mut Peekable<impl Iterator<Item = &'a MarkdownHeading>>,
+    parent_level: u8,
+) -> Vec<TocEntry> {
+    let mut entries = Vec::new();
+    while let Some(heading) =
+        headings.next_if(|heading| heading.level > parent_level)
+    {
+        let children = nest_headings(headings, heading.level);
+        entries.push(TocEntry {
+            level: heading.level,
+            text: heading.text.clone(),
+            id: heading.id.clone(),
+            children,
+        });
+    }
+
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::templates::default_md_parse_options;
+    use rstest::*;
+
+    fn parse(input: &str) -> Node {
+        markdown::to_mdast(input, &default_md_parse_options(false))
+            .expect("This should not fail")
+    }
+
+    fn ids(input: &str) -> Vec<String> {
+        collect_md_headings(&mut parse(input))
+            .into_iter()
+            .map(|heading| heading.id)
+            .collect()
+    }
+
+    #[rstest]
+    #[case("Hello, World!", "hello-world")]
+    #[case("  Trimmed  ", "trimmed")]
+    #[case("snake_case and kebab-case", "snake_case-and-kebab-case")]
+    #[case("Ünïcödé 123", "ünïcödé-123")]
+    #[case("🚀", "section")]
+    #[case("!!! ...", "section")]
+    fn slugify_works(#[case] input: &str, #[case] expected: &str) {
+        assert_eq!(slugify(input), expected);
+    }
+
+    #[test]
+    fn collect_headings_deduplicates_ids() {
+        assert_eq!(
+            ids("# Intro\n\n## Intro\n\n## *Intro*\n\n> ### Quoted"),
+            ["intro", "intro-1", "intro-2", "quoted"]
+        );
+    }
+
+    #[test]
+    fn collect_headings_falls_back_to_section() {
+        assert_eq!(
+            ids("# 🚀\n\n## ???\n\n## Section"),
+            ["section", "section-1", "section-2"]
+        );
+    }
+
+    #[test]
+    fn collect_headings_uses_explicit_ids() {
+        let mut tree = parse("# Intro {#start}\n\n## Intro\n\n## Start");
+
+        let headings = collect_md_headings(&mut tree);
+        let ids: Vec<_> = headings.iter().map(|h| h.id.as_str()).collect();
+        assert_eq!(ids, ["start", "intro", "start-1"]);
+        assert_eq!(headings[0].text, "Intro");
+        assert_eq!(tree.to_string(), "IntroIntroStart");
+    }
+
+    #[test]
+    fn collect_headings_deduplicates_explicit_ids() {
+        assert_eq!(
+            ids("# One {#same}\n\n## Two {#same}\n\n## Same"),
+            ["same", "same-1", "same-2"]
+        );
+    }
+
+    #[rstest]
+    #[case("# Not {#an id}", "not-an-id")]
+    #[case("# Empty {#}", "empty")]
+    #[case("# Code `{#x}`", "code-x")]
+    #[case("# Quote {#x\"y}", "quote-xy")]
+    fn collect_headings_ignores_invalid_attributes(
+        #[case] input: &str,
+        #[case] expected: &str,
+    ) {
+        assert_eq!(ids(input), [expected]);
+    }
+
+    #[rstest]
+    #[case::demote(1, "# One\n\n## Two\n\n###### Six", [2, 3, 6])]
+    #[case::promote(-2, "# One\n\n### Three\n\n###### Six", [1, 1, 4])]
+    #[case::nothing(0, "# One\n\n## Two\n\n### Three", [1, 2, 3])]
+    fn shift_headings_clamps_levels(
+        #[case] offset: i8,
+        #[case] input: &str,
+        #[case] expected: [u8; 3],
+    ) {
+        let mut tree = parse(input);
+        shift_md_headings(&mut tree, offset);
+
+        let levels: Vec<_> = collect_md_headings(&mut tree)
+            .into_iter()
+            .map(|heading| heading.level)
+            .collect();
+        assert_eq!(levels, expected);
+    }
+
+    #[test]
+    fn build_toc_nests_headings() {
+        let mut tree =
+            parse("# One\n\n## Two\n\n### Three\n\n## Four\n\n# Five");
+
+        let toc = build_md_toc(&collect_md_headings(&mut tree));
+        assert_eq!(toc.len(), 2);
+        assert_eq!(toc[0].text, "One");
+        assert_eq!(toc[0].children.len(), 2);
+        assert_eq!(toc[0].children[0].children[0].id, "three");
+        assert_eq!(toc[0].children[1].text, "Four");
+        assert_eq!(toc[1].text, "Five");
+        assert!(toc[1].children.is_empty());
+    }
+
+    #[test]
+    fn build_toc_handles_skipped_levels() {
+        let mut tree = parse("### Deep\n\n# Top\n\n### Nested");
+
+        let toc = build_md_toc(&collect_md_headings(&mut tree));
+        assert_eq!(toc.len(), 2);
+        assert_eq!(toc[0].level, 3);
+        assert_eq!(toc[1].children[0].text, "Nested");
+    }
+
+    #[test]
+    fn build_toc_is_empty_without_headings() {
+        let mut tree = parse("Just a paragraph");
+
+        assert!(build_md_toc(&collect_md_headings(&mut tree)).is_empty());
+    }
+}
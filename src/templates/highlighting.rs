@@ -0,0 +1,263 @@
+use super::merge_attribute;
+use std::{fmt::Write, sync::LazyLock};
+use syntect::{
+    easy::HighlightLines,
+    highlighting::ThemeSet,
+    html::{
+        ClassStyle, ClassedHTMLGenerator, IncludeBackground,
+        css_for_theme_with_class_style, styled_line_to_highlighted_html,
+    },
+    parsing::{SyntaxReference, SyntaxSet},
+    util::LinesWithEndings,
+};
+
+static SYNTAXES: LazyLock<SyntaxSet> =
+    LazyLock::new(SyntaxSet::load_defaults_newlines);
+static THEMES: LazyLock<ThemeSet> = LazyLock::new(ThemeSet::load_defaults);
+
+/// Theme used for inline styles when none is chosen.
+pub const DEFAULT_HIGHLIGHT_THEME: &str = "InspiredGitHub";
+
+/// How highlighted code blocks are styled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CodeHighlighting {
+    /// Inline `style` attributes with the colors of one of the bundled themes.
+    Inline { theme: String },
+    /// CSS classes named after the scopes of each token, to be styled by a stylesheet such as the
+    /// ones from [`highlight_stylesheet`].
+    Classes,
+}
+
+/// Returns the names of the bundled highlighting themes.
+pub fn highlight_themes() -> impl Iterator<Item = &'static str> {
+    THEMES.themes.keys().map(String::as_str)
+}
+
+/// Returns the CSS that colors code highlighted with [`CodeHighlighting::Classes`] like one of the
+/// bundled themes, or `None` if the theme is unknown.
+#[must_use]
+pub fn highlight_stylesheet(theme: &str) -> Option<String> {
+    let theme = THEMES.themes.get(theme)?;
+    css_for_theme_with_class_style(theme, ClassStyle::Spaced).ok()
+}
+
+/// Highlights `code` written in the language `lang` and returns the HTML for its code block, with
+/// the extra `pre_attributes` and `code_attributes` on its `<pre>` and `<code>` elements.
+///
+/// Returns `None` when the language or the theme are unknown, so the caller can fall back to an
+/// unhighlighted block.
+#[must_use]
+pub fn highlight_code(
+    code: &str,
+    lang: &str,
+    highlighting: &CodeHighlighting,
+    attributes: &[(String, String)],
+    code_attributes: &[(String, String)],
+) -> Option<String> {
+    let syntax = find_syntax(lang)?;
+    let code = format!("{code}\n");
+    let class = format!("language-{lang}");
+
+    let mut pre_attributes = Vec::new();
+    let html = match highlighting {
+        CodeHighlighting::Inline { theme } => {
+            let theme = THEMES.themes.get(theme)?;
+            let mut highlighter = HighlightLines::new(syntax, theme);
+
+            let mut html = String::new();
+            for line in LinesWithEndings::from(&code) {
+                let regions =
+                    highlighter.highlight_line(line, &SYNTAXES).ok()?;
+                html.push_str(
+                    &styled_line_to_highlighted_html(
+                        &regions,
+                        IncludeBackground::No,
+                    )
+                    .ok()?,
+                );
+            }
+
+            if let Some(color) = theme.settings.background {
+                pre_attributes.push((
+                    "style".to_string(),
+                    format!(
+                        "background-color:#{:02x}{:02x}{:02x};",
+                        color.r, color.g, color.b
+                    ),
+                ));
+            }
+
+            html
+        }
+        CodeHighlighting::Classes => {
+            let mut generator = ClassedHTMLGenerator::new_with_class_style(
+                syntax,
+                &SYNTAXES,
+                ClassStyle::Spaced,
+            );
+            for line in LinesWithEndings::from(&code) {
+                generator
+                    .parse_html_for_line_which_includes_newline(line)
+                    .ok()?;
+            }
+
+            // The stylesheet gives the background and default color to the `code` class.
+            pre_attributes.push(("class".to_string(), "code".to_string()));
+            generator.finalize()
+        }
+    };
+
+    for (name, value) in attributes {
+        merge_attribute(&mut pre_attributes, name, value);
+    }
+    let mut merged_code_attributes = vec![("class".to_string(), class)];
+    for (name, value) in code_attributes {
+        merge_attribute(&mut merged_code_attributes, name, value);
+    }
+
+    Some(format!(
+        "{}{}{html}</code></pre>",
+        open_tag("pre", &pre_attributes)?,
+        open_tag("code", &merged_code_attributes)?
+    ))
+}
+
+fn open_tag(tag: &str, attributes: &[(String, String)]) -> Option<String> {
+    let mut html = format!("<{tag}");
+    for (name, value) in attributes {
+        write!(html, " {name}=\"{}\"", super::encode(value)).ok()?;
+    }
+    html.push('>');
+
+    Some(html)
+}
+
+fn find_syntax(lang: &str) -> Option<&'static SyntaxReference> {
+    SYNTAXES
+        .find_syntax_by_token(lang)
+        .or_else(|| SYNTAXES.find_syntax_by_name(lang))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn inline() -> CodeHighlighting {
+        CodeHighlighting::Inline {
+            theme: DEFAULT_HIGHLIGHT_THEME.to_string(),
+        }
+    }
+
+    #[test]
+    fn highlight_code_uses_inline_styles() {
+        let result = highlight_code("let x = 1;", "rust", &inline(), &[], &[])
+            .expect("Failed to highlight code");
+
+        assert!(result.starts_with(
+            "<pre style=\"background-color:#ffffff;\"><code class=\"language-rust\">"
+        ));
+        assert!(result.contains("<span style=\"color:"));
+        assert!(result.ends_with("</code></pre>"));
+    }
+
+    #[test]
+    fn highlight_code_uses_classes() {
+        let result = highlight_code(
+            "let x = 1;",
+            "rs",
+            &CodeHighlighting::Classes,
+            &[],
+            &[],
+        )
+        .expect("Failed to highlight code");
+
+        assert!(result.starts_with(
+            "<pre class=\"code\"><code class=\"language-rs\"><span class=\"source rust\">"
+        ));
+        assert!(
+            result.contains("<span class=\"storage type rust\">let</span>")
+        );
+    }
+
+    #[test]
+    fn highlight_code_adds_attributes_to_pre_and_code() {
+        let attributes = [
+            ("class".to_string(), "code".to_string()),
+            ("data-copy".to_string(), "\"true\"".to_string()),
+        ];
+
+        let result = highlight_code(
+            "let x = 1;",
+            "rust",
+            &CodeHighlighting::Classes,
+            &attributes,
+            &[("class".to_string(), "block".to_string())],
+        )
+        .expect("Failed to highlight code");
+
+        assert!(result.starts_with(
+            "<pre class=\"code\" data-copy=\"&quot;true&quot;\"><code class=\"language-rust block\">"
+        ));
+    }
+
+    #[test]
+    fn highlight_code_escapes_html() {
+        let result = highlight_code(
+            "<script>",
+            "html",
+            &CodeHighlighting::Classes,
+            &[],
+            &[],
+        )
+        .expect("Failed to highlight code");
+
+        assert!(!result.contains("<script>"));
+    }
+
+    #[test]
+    fn highlight_code_is_deterministic() {
+        let code = "fn main() {\n    println!(\"Hello\");\n}";
+
+        assert_eq!(
+            highlight_code(code, "rust", &inline(), &[], &[]),
+            highlight_code(code, "rust", &inline(), &[], &[])
+        );
+    }
+
+    #[test]
+    fn highlight_code_ignores_unknown_languages_and_themes() {
+        assert!(
+            highlight_code("x", "not-a-language", &inline(), &[], &[])
+                .is_none()
+        );
+        assert!(
+            highlight_code(
+                "x",
+                "rust",
+                &CodeHighlighting::Inline {
+                    theme: "not-a-theme".to_string()
+                },
+                &[],
+                &[]
+            )
+            .is_none()
+        );
+    }
+
+    #[test]
+    fn highlight_stylesheet_styles_the_classes() {
+        let css = highlight_stylesheet(DEFAULT_HIGHLIGHT_THEME)
+            .expect("Failed to build stylesheet");
+
+        assert!(css.contains(".code {"));
+        assert!(css.contains(".storage"));
+        assert!(highlight_stylesheet("not-a-theme").is_none());
+    }
+
+    #[test]
+    fn highlight_themes_include_the_default() {
+        assert!(
+            highlight_themes().any(|theme| theme == DEFAULT_HIGHLIGHT_THEME)
+        );
+    }
+}
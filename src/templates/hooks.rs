@@ -0,0 +1,200 @@
+use color_eyre::eyre::bail;
+use std::collections::BTreeMap;
+
+/// Name under which the attributes of links to other sites are configured.
+pub const EXTERNAL_LINK: &str = "a:external";
+/// Elements that can get extra attributes.
+pub const HOOK_ELEMENTS: [&str; 28] = [
+    "a",
+    EXTERNAL_LINK,
+    "blockquote",
+    "code",
+    "del",
+    "em",
+    "figcaption",
+    "figure",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "hr",
+    "img",
+    "li",
+    "ol",
+    "p",
+    "pre",
+    "strong",
+    "sup",
+    "table",
+    "tbody",
+    "td",
+    "th",
+    "thead",
+    "tr",
+];
+
+/// Customizations applied to the HTML elements that Markdown files are compiled into.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HtmlHooks {
+    /// Extra attributes of each element, keyed by tag name, such as `img` or `table`.
+    /// Links to other sites also get the attributes under [`EXTERNAL_LINK`].
+    pub attributes: BTreeMap<String, Vec<(String, String)>>,
+    /// Class of a `<div>` to wrap tables in, if any.
+    pub table_wrapper: Option<String>,
+}
+
+impl HtmlHooks {
+    /// Adds an extra attribute to every `element`. Adding an attribute twice replaces its value,
+    /// except for `class`, which gets both values' classes.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the attribute can't be added, as explained in [`check_attribute`].
+    pub fn add_attribute(
+        &mut self,
+        element: &str,
+        name: &str,
+        value: &str,
+    ) -> color_eyre::Result<()> {
+        check_attribute(element, name)?;
+        let attributes =
+            self.attributes.entry(element.to_lowercase()).or_default();
+        merge_attribute(attributes, name, value);
+
+        Ok(())
+    }
+
+    /// Returns the extra attributes of `elements`, merged like in [`Self::add_attribute`].
+    pub(super) fn attributes(
+        &self,
+        elements: &[&str],
+    ) -> Vec<(String, String)> {
+        let mut merged = Vec::new();
+        for (name, value) in elements
+            .iter()
+            .filter_map(|element| self.attributes.get(*element))
+            .flatten()
+        {
+            merge_attribute(&mut merged, name, value);
+        }
+
+        merged
+    }
+}
+
+/// Checks that every `element` can get an extra attribute `name`.
+///
+/// # Errors
+///
+/// Returns an error if `element` is not one of [`HOOK_ELEMENTS`], if `name` is not made of ASCII
+/// letters, digits, `-`, `_` and `:`, or if it's the `id` of a heading or a figure, which is
+/// generated for anchors and cross-references.
+pub fn check_attribute(element: &str, name: &str) -> color_eyre::Result<()> {
+    let element_name = element.to_lowercase();
+    if !HOOK_ELEMENTS.contains(&element_name.as_str()) {
+        bail!(
+            "Attributes can't be added to `{element}` elements, only to {}",
+            HOOK_ELEMENTS
+                .iter()
+                .map(|element| format!("`{element}`"))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+    }
+    let valid = |c: char| c.is_ascii_alphanumeric() || "-_:".contains(c);
+    if name.is_empty() || !name.chars().all(valid) {
+        bail!("Invalid attribute name `{name}`");
+    }
+    let generated_id = matches!(
+        element_name.as_str(),
+        "h1" | "h2" | "h3" | "h4" | "h5" | "h6" | "figure"
+    );
+    if generated_id && name.eq_ignore_ascii_case("id") {
+        bail!(
+            "The `id` of `{element}` elements is generated, and can't be set"
+        );
+    }
+
+    Ok(())
+}
+
+/// Sets the attribute `name` to `value`, or adds the classes of `value` if it's a `class`.
+pub(super) fn merge_attribute(
+    attributes: &mut Vec<(String, String)>,
+    name: &str,
+    value: &str,
+) {
+    let existing = attributes
+        .iter_mut()
+        .find(|(existing, _)| existing.eq_ignore_ascii_case(name));
+
+    match existing {
+        Some((_, classes)) if name.eq_ignore_ascii_case("class") => {
+            for class in value.split_whitespace() {
+                if !classes.split_whitespace().any(|other| other == class) {
+                    if !classes.is_empty() {
+                        classes.push(' ');
+                    }
+                    classes.push_str(class);
+                }
+            }
+        }
+        Some((_, existing)) => value.clone_into(existing),
+        None => attributes.push((name.to_string(), value.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rstest::*;
+
+    #[test]
+    fn repeated_attributes_are_merged() {
+        let mut hooks = HtmlHooks::default();
+        hooks
+            .add_attribute("a", "class", "link")
+            .expect("Failed to add attribute");
+        hooks
+            .add_attribute("a", "rel", "nofollow")
+            .expect("Failed to add attribute");
+        hooks
+            .add_attribute("A", "class", "link fancy")
+            .expect("Failed to add attribute");
+        hooks
+            .add_attribute("a:external", "class", "external")
+            .expect("Failed to add attribute");
+        hooks
+            .add_attribute("a:external", "rel", "noopener")
+            .expect("Failed to add attribute");
+
+        assert_eq!(
+            hooks.attributes(&["a", EXTERNAL_LINK]),
+            [
+                ("class".to_string(), "link fancy external".to_string()),
+                ("rel".to_string(), "noopener".to_string()),
+            ]
+        );
+    }
+
+    #[rstest]
+    #[case::empty_name("img", "")]
+    #[case::quote_in_name("img", "on\"x")]
+    #[case::space_in_name("img", "on load")]
+    #[case::heading_id("H2", "ID")]
+    #[case::figure_id("figure", "id")]
+    #[case::unsupported_element("div", "class")]
+    #[case::unknown_element("blink", "class")]
+    fn invalid_attributes_are_errors(
+        #[case] element: &str,
+        #[case] name: &str,
+    ) {
+        let mut hooks = HtmlHooks::default();
+
+        assert!(hooks.add_attribute(element, name, "x").is_err());
+        assert!(hooks.attributes.is_empty());
+    }
+}
@@ -0,0 +1,427 @@
+use super::{callouts::CodeBlocks, extraction::frontmatter_length};
+use camino::{Utf8Path, Utf8PathBuf};
+use color_eyre::eyre::{Context, bail, eyre};
+use std::borrow::Cow;
+
+/// Where the `<!-- include: path -->` directives of a Markdown file are resolved.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Includes {
+    /// Path of the input, which the included paths are relative to.
+    pub current: Utf8PathBuf,
+    /// Directory that included files have to be inside of.
+    pub root: Utf8PathBuf,
+}
+
+/// Splices the files referenced by the `<!-- include: path -->` directives of a Markdown `&str`
+/// into it.
+///
+/// Markdown files are included without their frontmatter and have their own directives expanded.
+/// Any other file is included as a fenced code block, with its extension as the language. A
+/// `lines=start-end` argument, such as `lines=10-20`, `lines=10-` or `lines=10`, includes only
+/// that range of lines. Directives inside fenced or indented code blocks and the frontmatter are
+/// left untouched.
+///
+/// If `templated` is set, code blocks are wrapped in `{% raw %}` tags so that their code is kept
+/// verbatim when the body is rendered by [`render_md_template`](super::render_md_template).
+///
+/// # Errors
+///
+/// Returns an error if an included file can't be read or is outside of the root, if a line range
+/// is invalid or if a file ends up including itself.
+pub fn expand_md_includes<'a>(
+    input: &'a str,
+    includes: &Includes,
+    templated: bool,
+) -> color_eyre::Result<Cow<'a, str>> {
+    if !input.contains("<!--") {
+        return Ok(Cow::Borrowed(input));
+    }
+
+    let root = includes.root.canonicalize_utf8().wrap_err_with(|| {
+        format!("Failed to open includes root `{}`", includes.root)
+    })?;
+    let mut stack: Vec<_> =
+        includes.current.canonicalize_utf8().into_iter().collect();
+    let directory = includes
+        .current
+        .parent()
+        .unwrap_or_else(|| Utf8Path::new(""));
+    let (frontmatter, body) = input.split_at(frontmatter_length(input));
+
+    Ok(Cow::Owned(
+        frontmatter.to_string()
+            + &expand(body, directory, &root, &mut stack, templated)?,
+    ))
+}
+
+fn expand(
+    input: &str,
+    directory: &Utf8Path,
+    root: &Utf8Path,
+    stack: &mut Vec<Utf8PathBuf>,
+    templated: bool,
+) -> color_eyre::Result<String> {
+    let mut output = String::with_capacity(input.len());
+    let mut code_blocks = CodeBlocks::new();
+    for line in input.split_inclusive('\n') {
+        let trimmed = line.trim();
+
+        if !code_blocks.contains(line)
+            && let Some(include) = Include::parse(trimmed)?
+        {
+            let indent = &line[..line.len() - line.trim_start().len()];
+            let content = include.read(directory, root, stack, templated)?;
+            let content = if line.ends_with('\n') {
+                content.as_str()
+            } else {
+                content.trim_end_matches('\n')
+            };
+            for included in content.split_inclusive('\n') {
+                if !included.trim().is_empty() {
+                    output.push_str(indent);
+                }
+                output.push_str(included);
+            }
+            if line.ends_with('\n') && !content.ends_with('\n') {
+                output.push('\n');
+            }
+            continue;
+        }
+
+        output.push_str(line);
+    }
+
+    Ok(output)
+}
+
+/// An `<!-- include: path lines=start-end -->` directive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Include {
+    path: Utf8PathBuf,
+    /// First and last lines to include, starting at 1.
+    lines: Option<(usize, Option<usize>)>,
+}
+
+impl Include {
+    /// Parses a directive, returning `None` if `line` is not one.
+    fn parse(line: &str) -> color_eyre::Result<Option<Self>> {
+        let Some(directive) = line
+            .strip_prefix("<!--")
+            .and_then(|line| line.strip_suffix("-->"))
+            .and_then(|line| line.trim().strip_prefix("include:"))
+            .map(str::trim)
+        else {
+            return Ok(None);
+        };
+
+        let (path, arguments) = if let Some(quoted) =
+            directive.strip_prefix('"')
+        {
+            quoted.split_once('"').ok_or_else(|| {
+                eyre!("Unterminated path in `<!-- include: {directive} -->`")
+            })?
+        } else {
+            directive
+                .split_once(char::is_whitespace)
+                .unwrap_or((directive, ""))
+        };
+        if path.is_empty() {
+            bail!("Missing path in `<!-- include: {directive} -->`");
+        }
+
+        let lines = match arguments.trim() {
+            "" => None,
+            arguments => Some(
+                arguments
+                    .strip_prefix("lines=")
+                    .and_then(line_range)
+                    .ok_or_else(|| {
+                        eyre!(
+                            "Invalid arguments `{arguments}` to include `{path}`"
+                        )
+                    })?,
+            ),
+        };
+
+        Ok(Some(Self {
+            path: Utf8PathBuf::from(path),
+            lines,
+        }))
+    }
+
+    /// Reads the included file, relative to `directory`, as Markdown.
+    fn read(
+        &self,
+        directory: &Utf8Path,
+        root: &Utf8Path,
+        stack: &mut Vec<Utf8PathBuf>,
+        templated: bool,
+    ) -> color_eyre::Result<String> {
+        let path = directory.join(&self.path);
+        let canonical = path
+            .canonicalize_utf8()
+            .wrap_err_with(|| format!("Failed to include `{path}`"))?;
+        if !canonical.starts_with(root) {
+            bail!("`{path}` is outside of the includes root `{root}`");
+        }
+        if stack.contains(&canonical) {
+            bail!("`{path}` includes itself");
+        }
+        let content = std::fs::read_to_string(&canonical)
+            .wrap_err_with(|| format!("Failed to include `{path}`"))?;
+
+        let content = match self.lines {
+            Some((start, end)) => select_lines(&content, start, end)
+                .ok_or_else(|| {
+                    eyre!("`{path}` is shorter than the lines to include")
+                })?,
+            None if is_markdown(&path) => {
+                content[frontmatter_length(&content)..].to_string()
+            }
+            None => content,
+        };
+
+        if is_markdown(&path) {
+            stack.push(canonical);
+            let content = expand(
+                &content,
+                path.parent().unwrap_or_else(|| Utf8Path::new("")),
+                root,
+                stack,
+                templated,
+            );
+            stack.pop();
+
+            content
+        } else {
+            let block =
+                code_block(&content, path.extension().unwrap_or_default());
+            Ok(if templated {
+                raw_template(&block)
+            } else {
+                block
+            })
+        }
+    }
+}
+
+fn line_range(range: &str) -> Option<(usize, Option<usize>)> {
+    let (start, end) = match range.split_once('-') {
+        Some((start, "")) => (start.parse().ok()?, None),
+        Some((start, end)) => (start.parse().ok()?, Some(end.parse().ok()?)),
+        None => {
+            let line = range.parse().ok()?;
+            (line, Some(line))
+        }
+    };
+
+    (start > 0 && end.is_none_or(|end| end >= start)).then_some((start, end))
+}
+
+fn select_lines(
+    content: &str,
+    start: usize,
+    end: Option<usize>,
+) -> Option<String> {
+    let lines: Vec<_> = content.split_inclusive('\n').collect();
+    let end = end.unwrap_or(lines.len());
+
+    (start <= end && end <= lines.len()).then(|| lines[start - 1..end].concat())
+}
+
+fn is_markdown(path: &Utf8Path) -> bool {
+    matches!(path.extension(), Some("md" | "markdown"))
+}
+
+/// Wraps `content` in a fenced code block longer than any fence inside it.
+fn code_block(content: &str, language: &str) -> String {
+    let longest = content
+        .split(|c| c != '`')
+        .map(str::len)
+        .max()
+        .unwrap_or_default();
+    let fence = "`".repeat(longest.max(2) + 1);
+
+    format!(
+        "{fence}{language}\n{}\n{fence}\n",
+        content.trim_end_matches('\n')
+    )
+}
+
+/// Wraps `content` in a Tera `{% raw %}` block, ending it around any `{%` in `content` so that
+/// an `{% endraw %}` inside of it can't end the block early.
+fn raw_template(content: &str) -> String {
+    format!(
+        "{{% raw %}}{}{{% endraw %}}\n",
+        content
+            .trim_end_matches('\n')
+            .replace("{%", "{% endraw %}{{ \"{%\" }}{% raw %}")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::templates::{
+        MarkdownOptions, collect_md_headings, parse_md, render_md_html,
+        test_files::TempFiles,
+    };
+    use rstest::*;
+
+    fn render(dir: &TempFiles, input: &str) -> color_eyre::Result<String> {
+        let options = MarkdownOptions {
+            includes: Some(Includes {
+                current: dir.root.join("index.md"),
+                root: dir.root.clone(),
+            }),
+            ..MarkdownOptions::default()
+        };
+        let mut ast = parse_md(input, &options)?;
+        let headings = collect_md_headings(&mut ast);
+
+        Ok(render_md_html(&ast, &headings, &options))
+    }
+
+    #[fixture]
+    fn dir() -> TempFiles {
+        TempFiles::new(&[
+            ("index.md", ""),
+            (
+                "snippets/legal.md",
+                "+++\ntitle = \"Legal\"\n+++\nAll *rights* reserved.\n\n\
+                <!-- include: notice.md -->\n",
+            ),
+            ("snippets/notice.md", "Some notice."),
+            ("snippets/greeting.md", "Hello from {{ name }}."),
+            (
+                "src/template.html",
+                "<p>{{ name }} {{ missing }}</p>\n{%- endraw %}{% if x %}\n",
+            ),
+            ("src/main.rs", "fn main() {\n    println!(\"```\");\n}\n"),
+            ("loop.md", "<!-- include: snippets/../loop.md -->"),
+            ("self.md", "<!-- include: index.md -->"),
+        ])
+    }
+
+    #[rstest]
+    fn markdown_files_are_spliced(dir: TempFiles) {
+        assert_eq!(
+            render(
+                &dir,
+                "# Title\n\n<!-- include: snippets/legal.md -->\n\nEnd"
+            )
+            .expect("Failed to include files"),
+            "<h1 id=\"title\">Title</h1>\n<p>All <em>rights</em> reserved.</p>\n\
+            <p>Some notice.</p>\n<p>End</p>"
+        );
+    }
+
+    #[rstest]
+    #[case::whole_file(
+        "<!-- include: src/main.rs -->",
+        "<pre><code class=\"language-rs\">fn main() {\n    println!(&quot;```&quot;);\n}\n\
+        </code></pre>"
+    )]
+    #[case::line_range(
+        "<!-- include: \"src/main.rs\" lines=2 -->",
+        "<pre><code class=\"language-rs\">    println!(&quot;```&quot;);\n</code></pre>"
+    )]
+    #[case::open_range(
+        "- Item\n\n  <!-- include: src/main.rs lines=3- -->",
+        "<ul>\n<li>\n<p>Item</p>\n<pre><code class=\"language-rs\">}\n</code></pre>\n\
+        </li>\n</ul>"
+    )]
+    fn other_files_are_included_as_code(
+        dir: TempFiles,
+        #[case] input: &str,
+        #[case] expected: &str,
+    ) {
+        assert_eq!(
+            render(&dir, input).expect("Failed to include files"),
+            expected
+        );
+    }
+
+    #[rstest]
+    fn code_is_kept_verbatim_when_rendering_the_body(dir: TempFiles) {
+        let options = MarkdownOptions {
+            includes: Some(Includes {
+                current: dir.root.join("index.md"),
+                root: dir.root.clone(),
+            }),
+            render_body: true,
+            variables: [("name".to_string(), "jango".to_string())].into(),
+            ..MarkdownOptions::default()
+        };
+        let mut ast = parse_md(
+            "{{ name }}\n\n<!-- include: snippets/greeting.md -->\n\n\
+            <!-- include: src/template.html -->",
+            &options,
+        )
+        .expect("Failed to include files");
+        let headings = collect_md_headings(&mut ast);
+
+        assert_eq!(
+            render_md_html(&ast, &headings, &options),
+            "<p>jango</p>\n<p>Hello from jango.</p>\n\
+            <pre><code class=\"language-html\">&lt;p&gt;{{ name }} {{ missing }}&lt;/p&gt;\n\
+            {%- endraw %}{% if x %}\n</code></pre>"
+        );
+    }
+
+    #[rstest]
+    #[case::code_block(
+        "```\n<!-- include: src/main.rs -->\n```",
+        "<pre><code>&lt;!-- include: src/main.rs --&gt;\n</code></pre>"
+    )]
+    #[case::indented_code(
+        "Text\n\n    <!-- include: src/main.rs -->",
+        "<p>Text</p>\n<pre><code>&lt;!-- include: src/main.rs --&gt;\n</code></pre>"
+    )]
+    #[case::indented_code_in_list(
+        "- Item\n\n      <!-- include: src/main.rs -->",
+        "<ul>\n<li>\n<p>Item</p>\n<pre><code>&lt;!-- include: src/main.rs --&gt;\n\
+        </code></pre>\n</li>\n</ul>"
+    )]
+    #[case::other_comment(
+        "<!-- a comment -->",
+        "<p>&lt;!-- a comment --&gt;</p>"
+    )]
+    fn directives_in_code_are_kept(
+        dir: TempFiles,
+        #[case] input: &str,
+        #[case] expected: &str,
+    ) {
+        assert_eq!(
+            render(&dir, input).expect("Failed to include files"),
+            expected
+        );
+    }
+
+    #[rstest]
+    fn includes_outside_of_the_root_are_errors(dir: TempFiles) {
+        let options = MarkdownOptions {
+            includes: Some(Includes {
+                current: dir.root.join("snippets/legal.md"),
+                root: dir.root.join("snippets"),
+            }),
+            ..MarkdownOptions::default()
+        };
+
+        assert!(parse_md("<!-- include: notice.md -->", &options).is_ok());
+        assert!(
+            parse_md("<!-- include: ../src/main.rs -->", &options).is_err()
+        );
+    }
+
+    #[rstest]
+    #[case::missing_file("<!-- include: missing.md -->")]
+    #[case::cycle("<!-- include: loop.md -->")]
+    #[case::self_include("<!-- include: self.md -->")]
+    #[case::out_of_range("<!-- include: src/main.rs lines=2-9 -->")]
+    #[case::invalid_range("<!-- include: src/main.rs lines=3-1 -->")]
+    #[case::unknown_argument("<!-- include: src/main.rs lang=rust -->")]
+    fn invalid_includes_are_errors(dir: TempFiles, #[case] input: &str) {
+        assert!(render(&dir, input).is_err());
+    }
+}
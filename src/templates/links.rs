@@ -0,0 +1,162 @@
+use super::MarkdownOptions;
+use markdown::mdast::Node;
+
+/// Rewrites the relative URLs of the links, images and link definitions of a Markdown ast.
+///
+/// Links to `.md` files point to their `.html` output if `options.html_links` is set, and every
+/// relative URL is prefixed with `options.base_url`, if any.
+pub fn rewrite_md_links(root: &mut Node, options: &MarkdownOptions) {
+    if !options.html_links && options.base_url.is_none() {
+        return;
+    }
+
+    let (url, is_link) = match root {
+        Node::Link(link) => (Some(&mut link.url), true),
+        Node::Definition(definition) => (Some(&mut definition.url), true),
+        Node::Image(image) => (Some(&mut image.url), false),
+        _ => (None, false),
+    };
+
+    if let Some(url) = url.filter(|url| is_relative(url)) {
+        if is_link && options.html_links {
+            *url = html_link(url);
+        }
+        if let Some(base) = &options.base_url {
+            *url = rebase(url, base);
+        }
+    }
+
+    for child in root.children_mut().into_iter().flatten() {
+        rewrite_md_links(child, options);
+    }
+}
+
+/// Prefixes the relative URLs of the links, images and link definitions of a Markdown ast with
+/// the directory `base`.
+pub(super) fn rebase_md_links(root: &mut Node, base: &str) {
+    if base.is_empty() {
+        return;
+    }
+
+    let url = match root {
+        Node::Link(link) => Some(&mut link.url),
+        Node::Definition(definition) => Some(&mut definition.url),
+        Node::Image(image) => Some(&mut image.url),
+        _ => None,
+    };
+    if let Some(url) = url.filter(|url| is_relative(url)) {
+        *url = rebase(url, base);
+    }
+
+    for child in root.children_mut().into_iter().flatten() {
+        rebase_md_links(child, base);
+    }
+}
+
+fn is_relative(url: &str) -> bool {
+    let has_scheme = url
+        .find(':')
+        .is_some_and(|colon| !url[..colon].contains(['/', '?', '#']));
+
+    !(url.is_empty() || has_scheme || url.starts_with(['/', '#', '?']))
+}
+
+fn html_link(url: &str) -> String {
+    let end = url.find(['?', '#']).unwrap_or(url.len());
+    let (path, rest) = url.split_at(end);
+
+    path.strip_suffix(".md")
+        .or_else(|| path.strip_suffix(".markdown"))
+        .map_or_else(|| url.to_string(), |path| format!("{path}.html{rest}"))
+}
+
+fn rebase(url: &str, base: &str) -> String {
+    let url = url.trim_start_matches("./");
+    if base.is_empty() {
+        return url.to_string();
+    }
+
+    format!("{}/{url}", base.trim_end_matches('/'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::templates::parse_md_content;
+    use rstest::*;
+
+    #[rstest]
+    #[case::md_link("[a](other.md)", "<p><a href=\"other.html\">a</a></p>")]
+    #[case::fragment(
+        "[a](../docs/other.md#intro)",
+        "<p><a href=\"../docs/other.html#intro\">a</a></p>"
+    )]
+    #[case::reference(
+        "[a]\n\n[a]: other.markdown?x=1",
+        "<p><a href=\"other.html?x=1\">a</a></p>\n"
+    )]
+    #[case::other_file("[a](file.txt)", "<p><a href=\"file.txt\">a</a></p>")]
+    #[case::absolute(
+        "[a](https://example.com/other.md)",
+        "<p><a href=\"https://example.com/other.md\">a</a></p>"
+    )]
+    #[case::image(
+        "![a](image.md)",
+        "<p><img src=\"image.md\" alt=\"a\" /></p>"
+    )]
+    fn links_to_markdown_files_point_to_html(
+        #[case] input: &str,
+        #[case] expected: &str,
+    ) {
+        let options = MarkdownOptions {
+            html_links: true,
+            ..MarkdownOptions::default()
+        };
+
+        let result = parse_md_content(input, &options)
+            .expect("Failed to parse markdown input");
+        assert_eq!(result, expected);
+    }
+
+    #[rstest]
+    #[case::image(
+        "![a](./images/a.png)",
+        "<p><img src=\"https://example.com/posts/images/a.png\" alt=\"a\" /></p>"
+    )]
+    #[case::link(
+        "[a](other.md)",
+        "<p><a href=\"https://example.com/posts/other.html\">a</a></p>"
+    )]
+    #[case::root_relative(
+        "![a](/a.png)",
+        "<p><img src=\"/a.png\" alt=\"a\" /></p>"
+    )]
+    #[case::fragment("[a](#intro)", "<p><a href=\"#intro\">a</a></p>")]
+    #[case::email(
+        "[a](mailto:me@example.com)",
+        "<p><a href=\"mailto:me@example.com\">a</a></p>"
+    )]
+    fn relative_urls_are_rebased(#[case] input: &str, #[case] expected: &str) {
+        let options = MarkdownOptions {
+            html_links: true,
+            base_url: Some("https://example.com/posts/".to_string()),
+            ..MarkdownOptions::default()
+        };
+
+        let result = parse_md_content(input, &options)
+            .expect("Failed to parse markdown input");
+        assert_eq!(result, expected);
+    }
+
+    #[rstest]
+    #[case("img.png", "..", "../img.png")]
+    #[case("./img.png", "", "img.png")]
+    #[case("img.png", "/static/", "/static/img.png")]
+    fn rebase_joins_paths(
+        #[case] url: &str,
+        #[case] base: &str,
+        #[case] expected: &str,
+    ) {
+        assert_eq!(rebase(url, base), expected);
+    }
+}
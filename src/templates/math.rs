@@ -0,0 +1,59 @@
+use math_core::{LatexToMathML, MathCoreConfig, MathDisplay};
+use std::sync::LazyLock;
+
+static CONVERTER: LazyLock<Option<LatexToMathML>> =
+    LazyLock::new(|| LatexToMathML::new(MathCoreConfig::default()).ok());
+
+/// Converts a LaTeX formula into `MathML`, either inline or as a block.
+///
+/// Returns `None` if the formula isn't valid LaTeX.
+#[must_use]
+pub fn render_math(latex: &str, display: bool) -> Option<String> {
+    let display = if display {
+        MathDisplay::Block
+    } else {
+        MathDisplay::Inline
+    };
+
+    CONVERTER
+        .as_ref()?
+        .convert_with_local_state(latex.trim(), display)
+        .ok()
+        .map(|result| result.mathml)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_math_works_inline() {
+        let result = render_math("x^2", false).expect("Failed to render math");
+
+        assert!(result.starts_with("<math>"));
+        assert!(result.contains("<msup><mi>x</mi><mn>2</mn></msup>"));
+    }
+
+    #[test]
+    fn render_math_works_as_a_block() {
+        let result =
+            render_math("\\frac{a}{b}", true).expect("Failed to render math");
+
+        assert!(result.starts_with("<math display=\"block\">"));
+        assert!(result.contains("<mfrac><mi>a</mi><mi>b</mi></mfrac>"));
+    }
+
+    #[test]
+    fn render_math_escapes_text() {
+        let result = render_math("a < b \\text{<b>}", false)
+            .expect("Failed to render math");
+
+        assert!(result.contains("&lt;"));
+        assert!(!result.contains("<b>"));
+    }
+
+    #[test]
+    fn render_math_errors_on_invalid_latex() {
+        assert!(render_math("\\frac{a", false).is_none());
+    }
+}
@@ -1,20 +1,273 @@
+mod callouts;
+mod citations;
+mod crossrefs;
+mod diagrams;
 mod extraction;
+mod figures;
+mod headings;
+mod highlighting;
+mod hooks;
+mod includes;
+mod links;
+mod math;
+mod options;
 mod parsing;
 mod processing;
+mod rendering;
+mod sanitizer;
+mod sections;
+mod shortcodes;
+mod slides;
+mod statistics;
+mod summary;
+mod templating;
+#[cfg(test)]
+mod test_files;
+mod typography;
+mod wikilinks;
 
+pub use callouts::*;
+pub use citations::*;
+pub use crossrefs::*;
+pub use diagrams::*;
 pub use extraction::*;
-use markdown::{CompileOptions, Constructs, Options, ParseOptions};
+pub use figures::*;
+pub use headings::*;
+pub use highlighting::*;
+pub use hooks::*;
+pub use includes::*;
+pub use links::*;
+pub use math::*;
+pub use options::*;
 pub use parsing::*;
 pub use processing::*;
+pub use rendering::*;
+pub use sanitizer::*;
+pub use sections::*;
+pub use shortcodes::*;
+pub use slides::*;
+pub use statistics::*;
+pub use summary::*;
+pub use templating::*;
+pub use typography::*;
+pub use wikilinks::*;
 
-fn default_md_options(inline_html: bool) -> Options {
-    Options {
-        parse: default_md_parse_options(inline_html),
-        compile: CompileOptions {
-            allow_dangerous_html: inline_html,
-            ..CompileOptions::gfm()
-        },
+use color_eyre::eyre::eyre;
+use markdown::{
+    Constructs, ParseOptions,
+    mdast::{
+        AttributeContent, AttributeValue, Html, Image, ImageReference,
+        MdxJsxAttribute, MdxJsxFlowElement, MdxJsxTextElement, Node,
+    },
+};
+use std::borrow::Cow;
+
+/// Parses a Markdown `&str` into an ast, applying the syntax extensions enabled in `options`.
+fn parse_md(
+    input: &str,
+    options: &MarkdownOptions,
+) -> color_eyre::Result<Node> {
+    let input = match &options.includes {
+        Some(includes) => {
+            expand_md_includes(input, includes, options.render_body)?
+        }
+        None => Cow::Borrowed(input),
+    };
+    let (input, mut shortcode_calls) = if options.shortcodes.is_some() {
+        let (input, calls) = extract_md_shortcodes(&input);
+        (Cow::Owned(input), calls)
+    } else {
+        (input, Vec::new())
+    };
+    let input = if options.render_body {
+        let (ctx, _) = md_template_context(&input, &options.variables)?;
+        for call in &mut shortcode_calls {
+            call.render_templates(&ctx)?;
+        }
+        Cow::Owned(render_md_template(&input, &options.variables)?)
+    } else {
+        input
+    };
+    let input = if options.callouts.is_some() {
+        Cow::Owned(expand_md_admonitions(&input).into_owned())
+    } else {
+        input
+    };
+
+    // Only MDX expressions can fail to parse.
+    let mut ast = markdown::to_mdast(&input, &md_parse_options(options))
+        .map_err(|message| eyre!("Failed to parse Markdown: {message}"))?;
+    restore_md_image_alts(&mut ast, &input, options);
+    // Later steps, such as typography and slides, expect the marker on its own.
+    separate_summary_marker(&mut ast);
+    if let Some(shortcodes) = &options.shortcodes {
+        expand_md_shortcodes(&mut ast, &shortcode_calls, shortcodes)?;
+    }
+    if options.heading_offset != 0 {
+        shift_md_headings(&mut ast, options.heading_offset);
+    }
+    if let Some(callouts) = &options.callouts {
+        transform_md_callouts(&mut ast, callouts);
     }
+
+    Ok(ast)
+}
+
+/// Restores the alt text of the images of a Markdown ast whose description holds other images or
+/// line breaks, which the ast leaves out of it.
+///
+/// The alt text is taken from the HTML that the image's source compiles into, so that it matches
+/// the one of [`markdown::to_html`].
+fn restore_md_image_alts(
+    node: &mut Node,
+    source: &str,
+    options: &MarkdownOptions,
+) {
+    let range = node
+        .position()
+        .map(|position| position.start.offset..position.end.offset);
+    let (alt, label) = match node {
+        Node::Image(Image { alt, .. }) => (alt, None),
+        Node::ImageReference(ImageReference { alt, label, .. }) => {
+            (alt, label.as_deref())
+        }
+        _ => {
+            for child in node.children_mut().into_iter().flatten() {
+                restore_md_image_alts(child, source, options);
+            }
+            return;
+        }
+    };
+    let Some(image) = range.and_then(|range| source.get(range)) else {
+        return;
+    };
+    if !(image.get(2..).is_some_and(|label| label.contains("!["))
+        || image.contains('\n'))
+    {
+        return;
+    }
+
+    // References need their definition to compile into an image.
+    let image = label.map_or_else(
+        || image.to_string(),
+        |label| format!("{image}\n\n[{label}]: #"),
+    );
+    let options = markdown::Options {
+        parse: md_parse_options(options),
+        ..markdown::Options::gfm()
+    };
+    if let Some(restored) = markdown::to_html_with_options(&image, &options)
+        .ok()
+        .as_deref()
+        .and_then(|html| html.split_once(" alt=\""))
+        .and_then(|(_, rest)| rest.split_once('"'))
+        .map(|(value, _)| {
+            value
+                .replace("&quot;", "\"")
+                .replace("&lt;", "<")
+                .replace("&gt;", ">")
+                .replace("&amp;", "&")
+        })
+    {
+        *alt = restored;
+    }
+}
+
+/// What [`resolve_md`] collected from a Markdown ast.
+struct ResolvedMd {
+    /// Compiled HTML of the bibliography of the cited entries.
+    bibliography: String,
+    figures: Vec<Figure>,
+    headings: Vec<MarkdownHeading>,
+    /// Problems found while resolving, such as figure ids used more than once.
+    warnings: Vec<String>,
+}
+
+/// Resolves the wikilinks, links, citations, figures and cross-references of a parsed Markdown
+/// ast, collects its headings and applies its typography transforms.
+fn resolve_md(
+    root: &mut Node,
+    frontmatter: &toml::Table,
+    options: &MarkdownOptions,
+) -> color_eyre::Result<ResolvedMd> {
+    let mut warnings = resolve_md_wikilinks(root, options)?;
+    rewrite_md_links(root, options);
+    let bibliography = match &options.citations {
+        Some(citations) => citations
+            .load(frontmatter)?
+            .map(|bibliography| {
+                let (html, citation_warnings) =
+                    resolve_md_citations(root, &bibliography);
+                warnings.extend(citation_warnings);
+                html
+            })
+            .unwrap_or_default(),
+        None => String::new(),
+    };
+    let mut headings = collect_md_headings(root);
+    if options.emoji || options.smart_punctuation {
+        // Typography is applied last, but the headings' text should match how they're rendered.
+        for (heading, node) in headings.iter_mut().zip(md_heading_nodes(root)) {
+            let mut node = node.clone();
+            apply_md_typography(&mut node, options);
+            heading.text = inline_text(&node);
+        }
+    }
+    let (figures, figure_warnings) = options
+        .figures
+        .as_ref()
+        .map(|figures| {
+            // References show the numbers of figures, so their captions have to as well.
+            let figures = Figures {
+                numbered: figures.numbered
+                    || options.cross_references.is_some(),
+                ..figures.clone()
+            };
+            transform_md_figures(root, &figures, &headings)
+        })
+        .unwrap_or_default();
+    warnings.extend(figure_warnings);
+    if let Some(cross_references) = &options.cross_references {
+        resolve_md_cross_references(
+            root,
+            &headings,
+            &figures,
+            options
+                .figures
+                .as_ref()
+                .map_or("Figure", |figures| figures.label.as_str()),
+            cross_references,
+        );
+    }
+
+    apply_md_typography(root, options);
+
+    Ok(ResolvedMd {
+        bibliography,
+        figures,
+        headings,
+        warnings,
+    })
+}
+
+fn md_parse_options(options: &MarkdownOptions) -> ParseOptions {
+    let inline_html = options.inline_html || options.sanitizer.is_some();
+    let mut parse_options = default_md_parse_options(inline_html);
+    if options.flavor == MarkdownFlavor::CommonMark {
+        parse_options.constructs = Constructs {
+            frontmatter: true,
+            html_flow: inline_html,
+            html_text: inline_html,
+            ..Constructs::default()
+        };
+    }
+    parse_options.constructs.math_flow = options.math;
+    parse_options.constructs.math_text = options.math;
+    for (construct, enabled) in &options.constructs {
+        construct.set(&mut parse_options.constructs, *enabled);
+    }
+
+    parse_options
 }
 
 fn default_md_parse_options(inline_html: bool) -> ParseOptions {
@@ -28,3 +281,76 @@ fn default_md_parse_options(inline_html: bool) -> ParseOptions {
         ..ParseOptions::gfm()
     }
 }
+
+/// Creates a node holding HTML generated by jango itself, which the renderer outputs as is
+/// instead of escaping or sanitizing it like the input's HTML.
+///
+/// The HTML is wrapped in an MDX JSX fragment, `<>...</>`, which the parser never produces because
+/// MDX JSX isn't one of the constructs it can parse.
+fn trusted_html(value: String) -> Node {
+    Node::MdxJsxTextElement(MdxJsxTextElement {
+        children: vec![Node::Html(Html {
+            value,
+            position: None,
+        })],
+        position: None,
+        name: None,
+        attributes: Vec::new(),
+    })
+}
+
+/// Returns the HTML node inside a node created by [`trusted_html`].
+fn trusted_html_node(node: &Node) -> Option<&Html> {
+    match node {
+        Node::MdxJsxTextElement(MdxJsxTextElement {
+            children,
+            name: None,
+            attributes,
+            ..
+        }) if attributes.is_empty() => match children.as_slice() {
+            [Node::Html(html)] => Some(html),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Creates an HTML element node with the given attributes, which the renderer outputs as is.
+fn html_element(
+    name: &str,
+    attributes: &[(&str, &str)],
+    children: Vec<Node>,
+) -> Node {
+    Node::MdxJsxFlowElement(MdxJsxFlowElement {
+        children,
+        position: None,
+        name: Some(name.to_string()),
+        attributes: attributes
+            .iter()
+            .map(|(name, value)| {
+                AttributeContent::Property(MdxJsxAttribute {
+                    name: (*name).to_string(),
+                    value: Some(AttributeValue::Literal((*value).to_string())),
+                })
+            })
+            .collect(),
+    })
+}
+
+/// Whether `node` is a block, as opposed to inline content such as text or links.
+const fn is_flow_content(node: &Node) -> bool {
+    matches!(
+        node,
+        Node::Paragraph(_)
+            | Node::Heading(_)
+            | Node::ThematicBreak(_)
+            | Node::Blockquote(_)
+            | Node::List(_)
+            | Node::Code(_)
+            | Node::Math(_)
+            | Node::Table(_)
+            | Node::Definition(_)
+            | Node::FootnoteDefinition(_)
+            | Node::MdxJsxFlowElement(_)
+    )
+}
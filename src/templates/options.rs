@@ -0,0 +1,131 @@
+use super::{
+    Callouts, Citations, CodeHighlighting, CrossReferences, Figures, HtmlHooks,
+    HtmlSanitizer, Includes, Shortcodes, Vault,
+};
+use markdown::Constructs;
+use std::collections::BTreeMap;
+
+/// Settings for how Markdown files are compiled into HTML.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct MarkdownOptions {
+    /// Where to resolve the input's `<!-- include: path -->` directives. Directives are left as
+    /// comments when unset.
+    pub includes: Option<Includes>,
+    /// Whether to render the body of the input as a Tera template before compiling it.
+    pub render_body: bool,
+    /// Variables available to the templates, besides the frontmatter's fields.
+    pub variables: BTreeMap<String, String>,
+    /// Templates that the input can call as shortcodes, if any.
+    pub shortcodes: Option<Shortcodes>,
+    /// Dialect of Markdown to parse.
+    pub flavor: MarkdownFlavor,
+    /// Constructs to enable or disable on top of the ones of `flavor`.
+    pub constructs: BTreeMap<MarkdownConstruct, bool>,
+    /// Whether to keep the input's inline HTML instead of escaping it.
+    pub inline_html: bool,
+    /// Sanitizer to keep the input's inline HTML through instead of escaping it, if any. Takes
+    /// precedence over `inline_html`.
+    pub sanitizer: Option<HtmlSanitizer>,
+    /// Number of levels to move headings down by, or up if negative.
+    pub heading_offset: i8,
+    /// Whether to add a self-link anchor to every heading.
+    pub heading_anchors: bool,
+    /// How to highlight fenced code blocks, if at all.
+    pub code_highlighting: Option<CodeHighlighting>,
+    /// Whether to parse `$...$` and `$$...$$` formulas and render them as `MathML`.
+    pub math: bool,
+    /// Whether to convert fenced code blocks tagged `bob` or `svgbob` from ASCII diagrams into
+    /// inline SVG images.
+    pub diagrams: bool,
+    /// Whether to replace `:name:` shortcodes with their emoji.
+    pub emoji: bool,
+    /// Whether to curl quotes and turn `--`, `---` and `...` into dashes and ellipses.
+    pub smart_punctuation: bool,
+    /// Number of words to summarize documents without a `<!-- more -->` marker with, instead of
+    /// their first paragraph.
+    pub summary_words: Option<usize>,
+    /// Where to find the bibliography that `[@key]` citations refer to, if anywhere.
+    pub citations: Option<Citations>,
+    /// Whether to leave the footnote section out of the compiled HTML, so it can be placed
+    /// separately.
+    pub separate_footnotes: bool,
+    /// How to turn images with a title into figures, if at all.
+    pub figures: Option<Figures>,
+    /// How to number sections, figures and tables and resolve `@sec:id`-style references to
+    /// them, if at all.
+    pub cross_references: Option<CrossReferences>,
+    /// Whether to also split the contents into a section for each heading.
+    pub sections: bool,
+    /// Whether to also split the contents into slides.
+    pub slides: bool,
+    /// How to render callouts and admonitions, if at all.
+    pub callouts: Option<Callouts>,
+    /// Whether to point relative links to Markdown files to their `.html` output.
+    pub html_links: bool,
+    /// URL or path to prefix relative link and image URLs with, if any.
+    pub base_url: Option<String>,
+    /// Extra attributes and wrappers of the compiled HTML elements.
+    pub hooks: HtmlHooks,
+    /// Vault to resolve `[[wikilinks]]` against, if any.
+    pub vault: Option<Vault>,
+}
+
+/// A dialect of Markdown.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MarkdownFlavor {
+    /// GitHub Flavored Markdown, with autolink literals, footnotes, strikethrough, tables and
+    /// task lists.
+    #[default]
+    Gfm,
+    /// Strict `CommonMark`.
+    CommonMark,
+}
+
+/// A piece of Markdown syntax that can be turned on or off.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MarkdownConstruct {
+    /// Links between angle brackets, like `<https://example.com>`.
+    Autolinks,
+    /// Bare URLs and emails, like `https://example.com`.
+    AutolinkLiterals,
+    /// Code blocks indented by four spaces.
+    IndentedCode,
+    /// Footnote references and definitions.
+    Footnotes,
+    /// `$...$` and `$$...$$` formulas.
+    Math,
+    /// `{...}` expressions, which are kept as text.
+    MdxExpressions,
+    /// `~~deleted~~` text.
+    Strikethrough,
+    /// Tables.
+    Tables,
+    /// `[ ]` and `[x]` checkboxes at the start of list items.
+    TaskLists,
+}
+
+impl MarkdownConstruct {
+    pub(super) const fn set(self, constructs: &mut Constructs, enabled: bool) {
+        match self {
+            Self::Autolinks => constructs.autolink = enabled,
+            Self::AutolinkLiterals => constructs.gfm_autolink_literal = enabled,
+            Self::IndentedCode => constructs.code_indented = enabled,
+            Self::Footnotes => {
+                constructs.gfm_footnote_definition = enabled;
+                constructs.gfm_label_start_footnote = enabled;
+            }
+            Self::Math => {
+                constructs.math_flow = enabled;
+                constructs.math_text = enabled;
+            }
+            Self::MdxExpressions => {
+                constructs.mdx_expression_flow = enabled;
+                constructs.mdx_expression_text = enabled;
+            }
+            Self::Strikethrough => constructs.gfm_strikethrough = enabled,
+            Self::Tables => constructs.gfm_table = enabled,
+            Self::TaskLists => constructs.gfm_task_list_item = enabled,
+        }
+    }
+}
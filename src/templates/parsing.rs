@@ -1,5 +1,8 @@
-use super::default_md_options;
+use super::{
+    MarkdownOptions, parse_md, render_md_html_and_footnotes, resolve_md,
+};
 use color_eyre::eyre::Context;
+use markdown::mdast::Node;
 
 /// Given a string representing a TOML table, it'll parse it and return a `toml::Table` with its
 /// values.
@@ -13,16 +16,44 @@ pub fn parse_md_frontmatter(
     toml::from_str(frontmatter).wrap_err("Failed to parse TOML fragment")
 }
 
-#[allow(clippy::missing_panics_doc)]
-#[must_use]
-pub fn parse_md_content(input: &str, inline_html: bool) -> String {
-    markdown::to_html_with_options(input, &default_md_options(inline_html))
-        .expect("This should never fail")
+/// Compiles a Markdown `&str` into HTML, giving every heading an anchor id.
+///
+/// Citations are resolved, but the bibliography isn't part of the output, and neither is the
+/// footnote section when it's separated: use [`process_md_file`](super::process_md_file) to get
+/// them.
+///
+/// # Errors
+///
+/// Returns an error if the input's frontmatter is not valid TOML, if its body fails to render as
+/// a template, if it contains invalid MDX expressions while they're enabled, if its
+/// bibliography can't be loaded or if its wikilinks embed pages that can't be read.
+pub fn parse_md_content(
+    input: &str,
+    options: &MarkdownOptions,
+) -> color_eyre::Result<String> {
+    let mut ast = parse_md(input, options)?;
+    let frontmatter = match ast.children().and_then(|nodes| nodes.first()) {
+        Some(Node::Toml(frontmatter)) => {
+            parse_md_frontmatter(&frontmatter.value)?
+        }
+        _ => toml::Table::new(),
+    };
+    let resolved = resolve_md(&mut ast, &frontmatter, options)?;
+    let (mut content, footnotes) =
+        render_md_html_and_footnotes(&ast, &resolved.headings, options);
+    if !options.separate_footnotes {
+        footnotes.append_to(&mut content);
+    }
+
+    Ok(content)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::templates::{
+        MarkdownConstruct, MarkdownFlavor, Vault, test_files::TempFiles,
+    };
     use rstest::*;
 
     #[fixture]
@@ -70,20 +101,168 @@ mod tests {
         let input =
             "# This is a title\n\nThis is a paragraph with a **bold** word.";
 
-        let result = parse_md_content(input, false);
+        let result = parse_md_content(input, &MarkdownOptions::default())
+            .expect("Failed to parse markdown input");
         assert_eq!(
             result,
-            "<h1>This is a title</h1>\n<p>This is a paragraph \
+            "<h1 id=\"this-is-a-title\">This is a title</h1>\n<p>This is a paragraph \
             with a <strong>bold</strong> word.</p>"
         );
     }
 
+    #[test]
+    fn parse_contents_shifts_headings() {
+        let options = MarkdownOptions {
+            heading_offset: 1,
+            ..MarkdownOptions::default()
+        };
+
+        let result = parse_md_content("# Title\n\n## Section", &options)
+            .expect("Failed to parse markdown input");
+        assert_eq!(
+            result,
+            "<h2 id=\"title\">Title</h2>\n<h3 id=\"section\">Section</h3>"
+        );
+    }
+
+    #[test]
+    fn parse_contents_resolves_wikilinks() {
+        let vault = TempFiles::new(&[
+            ("index.md", ""),
+            ("Page.md", "# Page\n\nEmbedded."),
+        ]);
+        let options = MarkdownOptions {
+            vault: Some(
+                Vault::load(&vault.root, &vault.root.join("index.md"))
+                    .expect("Failed to load vault"),
+            ),
+            ..MarkdownOptions::default()
+        };
+
+        let result = parse_md_content("See [[Page]].\n\n![[Page]]", &options)
+            .expect("Failed to parse markdown input");
+        assert_eq!(
+            result,
+            "<p>See <a href=\"Page.html\">Page</a>.</p>\n<div class=\"embed\">\n\
+            <h1 id=\"embed-1-page\">Page</h1>\n<p>Embedded.</p>\n</div>"
+        );
+    }
+
+    #[rstest]
+    #[case::appended(
+        false,
+        "<p>Text<sup><a href=\"#user-content-fn-a\" id=\"user-content-fnref-a\" \
+        data-footnote-ref=\"\" aria-describedby=\"footnote-label\">1</a></sup></p>\n\
+        <section data-footnotes=\"\" class=\"footnotes\"><h2 id=\"footnote-label\" \
+        class=\"sr-only\">Footnotes</h2>\n<ol>\n<li id=\"user-content-fn-a\">\n\
+        <p>Note <a href=\"#user-content-fnref-a\" data-footnote-backref=\"\" \
+        aria-label=\"Back to content\" class=\"data-footnote-backref\">↩</a></p>\n\
+        </li>\n</ol>\n</section>\n"
+    )]
+    #[case::separated(
+        true,
+        "<p>Text<sup><a href=\"#user-content-fn-a\" id=\"user-content-fnref-a\" \
+        data-footnote-ref=\"\" aria-describedby=\"footnote-label\">1</a></sup></p>\n"
+    )]
+    fn parse_contents_leaves_out_separated_footnotes(
+        #[case] separate_footnotes: bool,
+        #[case] expected: &str,
+    ) {
+        let options = MarkdownOptions {
+            separate_footnotes,
+            ..MarkdownOptions::default()
+        };
+
+        let result = parse_md_content("Text[^a]\n\n[^a]: Note", &options)
+            .expect("Failed to parse markdown input");
+        assert_eq!(result, expected);
+    }
+
+    #[rstest]
+    #[case::gfm(
+        MarkdownFlavor::Gfm,
+        &[],
+        "<p><a href=\"https://example.com\">https://example.com</a> <del>a</del></p>"
+    )]
+    #[case::commonmark(
+        MarkdownFlavor::CommonMark,
+        &[],
+        "<p>https://example.com ~~a~~</p>"
+    )]
+    #[case::disabled_construct(
+        MarkdownFlavor::Gfm,
+        &[(MarkdownConstruct::AutolinkLiterals, false)],
+        "<p>https://example.com <del>a</del></p>"
+    )]
+    #[case::enabled_construct(
+        MarkdownFlavor::CommonMark,
+        &[(MarkdownConstruct::Strikethrough, true)],
+        "<p>https://example.com <del>a</del></p>"
+    )]
+    fn parse_contents_uses_the_configured_constructs(
+        #[case] flavor: MarkdownFlavor,
+        #[case] constructs: &[(MarkdownConstruct, bool)],
+        #[case] expected: &str,
+    ) {
+        let options = MarkdownOptions {
+            flavor,
+            constructs: constructs.iter().copied().collect(),
+            ..MarkdownOptions::default()
+        };
+
+        let result = parse_md_content("https://example.com ~~a~~", &options)
+            .expect("Failed to parse markdown input");
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn parse_contents_keeps_mdx_expressions_as_text() {
+        let options = MarkdownOptions {
+            constructs: [(MarkdownConstruct::MdxExpressions, true)].into(),
+            ..MarkdownOptions::default()
+        };
+
+        assert_eq!(
+            parse_md_content("a {b} c\n\n{<d>}", &options)
+                .expect("Failed to parse markdown input"),
+            "<p>a {b} c</p>\n<p>{&lt;d&gt;}</p>"
+        );
+    }
+
+    #[test]
+    fn parse_contents_errors_on_unterminated_mdx_expressions() {
+        let options = MarkdownOptions {
+            constructs: [(MarkdownConstruct::MdxExpressions, true)].into(),
+            ..MarkdownOptions::default()
+        };
+
+        assert!(parse_md_content("a {b", &options).is_err());
+    }
+
+    #[test]
+    fn parse_contents_reads_ids_from_mdx_expressions() {
+        let options = MarkdownOptions {
+            constructs: [(MarkdownConstruct::MdxExpressions, true)].into(),
+            ..MarkdownOptions::default()
+        };
+
+        assert_eq!(
+            parse_md_content(
+                "# Intro {#custom}\n\n## Other { #other }",
+                &options
+            )
+            .expect("Failed to parse markdown input"),
+            "<h1 id=\"custom\">Intro</h1>\n<h2 id=\"other\">Other</h2>"
+        );
+    }
+
     #[rstest]
     fn parse_contents_works_on_a_file_with_a_frontmatter(md_test: &str) {
-        let result = parse_md_content(md_test, false);
+        let result = parse_md_content(md_test, &MarkdownOptions::default())
+            .expect("Failed to parse markdown input");
         assert_eq!(
             result,
-            "<h1>Lorem ipsum dolor sit amet</h1>\n<p>Lorem \
+            "<h1 id=\"lorem-ipsum-dolor-sit-amet\">Lorem ipsum dolor sit amet</h1>\n<p>Lorem \
             <del>ipsum</del> <em>dolor</em> sit amet, officia excepteur ex \
             fugiat reprehenderit enim labore culpa sint ad nisi Lorem pariatur \
             mollit ex esse <strong>exercitation</strong> amet. Nisi anim \
@@ -101,7 +280,13 @@ mod tests {
 
     #[rstest]
     fn parse_contents_works_with_valid_inline_html(html_test: &str) {
-        let result = parse_md_content(html_test, true);
+        let options = MarkdownOptions {
+            inline_html: true,
+            ..MarkdownOptions::default()
+        };
+
+        let result = parse_md_content(html_test, &options)
+            .expect("Failed to parse markdown input");
         assert_eq!(
             result,
             "<h1>Lorem ipsum dolor sit amet</h1>\n<p>Lorem \
@@ -1,34 +1,142 @@
 use super::{
-    default_md_parse_options, extract_md_frontmatter, parse_md_content,
-    parse_md_frontmatter,
+    Figure, Footnote, MarkdownOptions, ResolvedMd, Section, Slide, TocEntry,
+    build_md_toc, count_chars, count_words, extract_md_body,
+    extract_md_frontmatter, extract_md_prose, extract_md_summary,
+    extract_md_text, parse_md, parse_md_frontmatter, reading_time,
+    render_md_html, render_md_html_and_footnotes, resolve_md,
+    split_md_sections, split_md_slides,
 };
+use markdown::mdast::Node;
 
-/// Processes a markdown `&str` containing a TOML frontmatter and returns a tuple with a `Table`
-/// that corresponds to the frontmatter and a String corresponding to the compiled HTML of the
-/// input's contents.
+/// The values extracted from a Markdown file that are made available to templates.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MarkdownDocument {
+    /// Table parsed from the file's TOML frontmatter.
+    pub frontmatter: toml::Table,
+    /// Compiled HTML of the file's contents, with its footnote section unless it's separated.
+    pub content: String,
+    /// Compiled HTML of the file's summary.
+    pub summary: String,
+    /// Plain text rendering of the file's summary.
+    pub summary_text: String,
+    /// Markdown source of the file's contents, without its frontmatter.
+    pub raw_content: String,
+    /// Plain text rendering of the file's contents.
+    pub text_content: String,
+    /// Number of words in the file's contents, not counting code and math.
+    pub word_count: usize,
+    /// Number of characters in the file's contents, not counting whitespace, code and math.
+    pub char_count: usize,
+    /// Estimated minutes it takes to read the file's contents.
+    pub reading_time: usize,
+    /// Table of contents built from the file's headings.
+    pub toc: Vec<TocEntry>,
+    /// Footnotes of the file's contents, in the order they're referenced.
+    pub footnotes: Vec<Footnote>,
+    /// Compiled HTML of the file's footnote section, if it's separated from its contents.
+    pub footnotes_html: String,
+    /// Compiled HTML of the bibliography listing the entries cited in the file.
+    pub bibliography: String,
+    /// Figures made from the file's images, if enabled.
+    pub figures: Vec<Figure>,
+    /// Sections of the file's contents in document order, if enabled.
+    pub sections: Vec<Section>,
+    /// Slides the file's contents are split into, if enabled.
+    pub slides: Vec<Slide>,
+    /// Markdown ast of the whole file, after the transforms enabled in the options.
+    ///
+    /// The HTML generated by jango, such as rendered shortcodes, embedded pages and citations, is
+    /// an `html` node inside an `mdxJsxTextElement` fragment, without a name. Callouts, figures and
+    /// captioned tables are `mdxJsxFlowElement` nodes without a position, named after their HTML
+    /// element and with literal attributes: a `div` holding a `p` with the title and the callout's
+    /// blocks, and a `figure` holding the image or table and a `figcaption`.
+    pub ast: Node,
+    /// Problems found in the file that didn't stop its processing, such as unresolved wikilinks or
+    /// figure ids used more than once.
+    pub warnings: Vec<String>,
+}
+
+/// Processes a markdown `&str` containing a TOML frontmatter into a [`MarkdownDocument`].
+///
+/// The document holds the parsed frontmatter, the compiled HTML of the input's contents and of
+/// their summary, their Markdown source and plain text rendering, statistics about their text, a
+/// table of contents, its bibliography, its figures, its sections, its slides and the Markdown
+/// ast of the input.
 ///
 /// # Errors
 ///
-/// This function returns an error if it's unable to extract the TOML frontmatter or if the input's
-/// frontmatter is not valid TOML.
-#[allow(clippy::missing_panics_doc)]
+/// This function returns an error if it's unable to extract the TOML frontmatter, if the input's
+/// frontmatter is not valid TOML, if its includes can't be expanded, if its body fails to render
+/// as a template, if its bibliography can't be loaded, if its wikilinks embed pages that can't
+/// be read or if the attributes of its slides are invalid.
 pub fn process_md_file(
     input: &str,
-    inline_html: bool,
-) -> color_eyre::Result<(toml::Table, String)> {
-    let ast = markdown::to_mdast(input, &default_md_parse_options(inline_html))
-        .expect("This should never fail");
+    options: &MarkdownOptions,
+) -> color_eyre::Result<MarkdownDocument> {
+    let mut ast = parse_md(input, options)?;
     let frontmatter = extract_md_frontmatter(&ast)?;
     let frontmatter = parse_md_frontmatter(&frontmatter)?;
 
-    let content = parse_md_content(input, inline_html);
+    let ResolvedMd {
+        bibliography,
+        figures,
+        headings,
+        warnings,
+    } = resolve_md(&mut ast, &frontmatter, options)?;
+    let slides = if options.slides {
+        split_md_slides(&mut ast, &headings, options)?
+    } else {
+        Vec::new()
+    };
+    let summary = extract_md_summary(&mut ast, options.summary_words);
+    let (mut content, footnotes) =
+        render_md_html_and_footnotes(&ast, &headings, options);
+    let footnotes_html = if options.separate_footnotes {
+        footnotes.section.clone()
+    } else {
+        footnotes.append_to(&mut content);
+        String::new()
+    };
+    let raw_content = extract_md_body(input).to_string();
+    let text_content = extract_md_text(&ast);
+    let prose = extract_md_prose(&ast);
+    let word_count = count_words(&prose);
+    let char_count = count_chars(&prose);
+    let toc = build_md_toc(&headings);
+    let sections = if options.sections {
+        split_md_sections(&ast, &headings, options)
+    } else {
+        Vec::new()
+    };
+    let summary_text = extract_md_text(&summary);
+    let summary = render_md_html(&summary, &headings, options);
 
-    Ok((frontmatter, content))
+    Ok(MarkdownDocument {
+        frontmatter,
+        content,
+        summary,
+        summary_text,
+        raw_content,
+        text_content,
+        word_count,
+        char_count,
+        reading_time: reading_time(word_count),
+        toc,
+        footnotes: footnotes.notes,
+        footnotes_html,
+        bibliography,
+        figures,
+        sections,
+        slides,
+        ast,
+        warnings,
+    })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::templates::CrossReferences;
     use rstest::*;
 
     #[fixture]
@@ -51,21 +159,20 @@ mod tests {
 
     #[rstest]
     fn process_md_file_works_on_a_file_with_a_frontmatter(md_test: &str) {
-        let result = process_md_file(md_test, false);
+        let result = process_md_file(md_test, &MarkdownOptions::default());
         assert!(result.is_ok());
 
-        let (frontmatter, content) =
-            result.expect("Failed to parse markdown file");
+        let document = result.expect("Failed to parse markdown file");
 
         assert_eq!(
-            frontmatter["description"].as_str(),
+            document.frontmatter["description"].as_str(),
             Some("This is a test note")
         );
-        assert_eq!(frontmatter["date"].as_str(), Some("2024-04-03"));
+        assert_eq!(document.frontmatter["date"].as_str(), Some("2024-04-03"));
 
         assert_eq!(
-            content,
-            "<h1>Lorem ipsum dolor sit amet</h1>\n<p>Lorem \
+            document.content,
+            "<h1 id=\"lorem-ipsum-dolor-sit-amet\">Lorem ipsum dolor sit amet</h1>\n<p>Lorem \
             <del>ipsum</del> <em>dolor</em> sit amet, officia excepteur ex \
             fugiat reprehenderit enim labore culpa sint ad nisi Lorem pariatur \
             mollit ex esse <strong>exercitation</strong> amet. Nisi anim \
@@ -85,21 +192,174 @@ mod tests {
     fn process_md_file_works_on_a_file_with_an_empty_frontmatter() {
         let input = "+++\n+++\n# This is a heading\n\nThis is a paragraph";
 
-        let result = process_md_file(input, false);
+        let result = process_md_file(input, &MarkdownOptions::default());
         assert!(result.is_ok());
 
-        let (_, content) = result.expect("Failed to parse markdown input");
+        let document = result.expect("Failed to parse markdown input");
         assert_eq!(
-            content,
-            "<h1>This is a heading</h1>\n<p>This is a paragraph</p>"
+            document.content,
+            "<h1 id=\"this-is-a-heading\">This is a heading</h1>\n<p>This is a paragraph</p>"
         );
     }
 
+    #[test]
+    fn process_md_file_extracts_a_summary() {
+        let input = "+++\n+++\n\nThe *intro*.\n\n<!-- more -->\n\nThe rest.";
+
+        let document = process_md_file(input, &MarkdownOptions::default())
+            .expect("Failed to parse markdown input");
+
+        assert_eq!(document.summary, "<p>The <em>intro</em>.</p>");
+        assert_eq!(document.summary_text, "The intro.");
+        assert_eq!(
+            document.content,
+            "<p>The <em>intro</em>.</p>\n<p>The rest.</p>"
+        );
+    }
+
+    #[test]
+    fn process_md_file_renders_the_body_as_a_template() {
+        let input = "+++\nname = \"jango\"\n+++\n# {{ name }} {{ version }}";
+        let options = MarkdownOptions {
+            render_body: true,
+            variables: [("version".to_string(), "1.0".to_string())].into(),
+            ..MarkdownOptions::default()
+        };
+
+        let document = process_md_file(input, &options)
+            .expect("Failed to parse markdown input");
+
+        assert_eq!(document.content, "<h1 id=\"jango-10\">jango 1.0</h1>");
+        assert_eq!(document.frontmatter["name"].as_str(), Some("jango"));
+    }
+
+    #[rstest]
+    #[case::inline(false)]
+    #[case::separate(true)]
+    fn process_md_file_exposes_footnotes(#[case] separate_footnotes: bool) {
+        let input = "+++\n+++\nText[^note].\n\n[^note]: A *note*.";
+        let options = MarkdownOptions {
+            separate_footnotes,
+            ..MarkdownOptions::default()
+        };
+
+        let document = process_md_file(input, &options)
+            .expect("Failed to parse markdown input");
+
+        assert_eq!(
+            document.footnotes,
+            vec![Footnote {
+                number: 1,
+                id: "user-content-fn-note".to_string(),
+                reference_id: "user-content-fnref-note".to_string(),
+                content: "<p>A <em>note</em>.</p>".to_string(),
+            }]
+        );
+        assert_eq!(document.content.contains("<section"), !separate_footnotes);
+        assert_eq!(
+            document.footnotes_html.starts_with("<section"),
+            separate_footnotes
+        );
+    }
+
+    #[test]
+    fn process_md_file_exposes_the_markdown_source_and_text() {
+        let input = "+++\ntitle = \"Test\"\n+++\n\n# A heading\n\nSome *text*";
+
+        let document = process_md_file(input, &MarkdownOptions::default())
+            .expect("Failed to parse markdown input");
+
+        assert_eq!(document.raw_content, "# A heading\n\nSome *text*");
+        assert_eq!(document.text_content, "A heading\n\nSome text");
+        assert_eq!(document.word_count, 4);
+        assert_eq!(document.char_count, 16);
+        assert_eq!(document.reading_time, 1);
+        assert_eq!(document.toc[0].id, "a-heading");
+        assert!(matches!(document.ast, Node::Root(_)));
+    }
+
+    #[test]
+    fn process_md_file_exposes_generated_html_in_fragments() {
+        let input = "+++\n+++\n# A heading";
+        let options = MarkdownOptions {
+            cross_references: Some(CrossReferences::default()),
+            ..MarkdownOptions::default()
+        };
+
+        let document = process_md_file(input, &options)
+            .expect("Failed to parse markdown input");
+
+        assert!(document.content.contains("class=\"section-number\""));
+        let heading = &document.ast.children().expect("No children")[1];
+        let Some(Node::MdxJsxTextElement(fragment)) =
+            heading.children().and_then(|children| children.first())
+        else {
+            panic!("The section number is not a fragment");
+        };
+        assert!(fragment.name.is_none());
+        assert!(matches!(
+            fragment.children.as_slice(),
+            [Node::Html(html)] if html.value.contains("section-number")
+        ));
+    }
+
+    #[test]
+    fn process_md_file_does_not_count_code() {
+        let input = "+++\n+++\n\nRun `cargo build` now.\n\n```sh\ncargo build --release\n```";
+
+        let document = process_md_file(input, &MarkdownOptions::default())
+            .expect("Failed to parse markdown input");
+
+        assert_eq!(document.word_count, 2);
+        assert_eq!(document.char_count, 7);
+        assert!(document.text_content.contains("cargo build --release"));
+    }
+
+    #[test]
+    fn process_md_file_keeps_the_raw_body() {
+        let options = MarkdownOptions {
+            render_body: true,
+            ..MarkdownOptions::default()
+        };
+
+        let document = process_md_file(
+            "+++\ntitle = \"A\"\n+++\n\n# {{ title }}",
+            &options,
+        )
+        .expect("Failed to parse markdown input");
+
+        assert_eq!(document.raw_content, "# {{ title }}");
+        assert_eq!(document.text_content, "A");
+    }
+
+    #[rstest]
+    #[case::disabled(false, &[])]
+    #[case::enabled(true, &["b", "a"])]
+    fn process_md_file_splits_sections_when_enabled(
+        #[case] sections: bool,
+        #[case] ids: &[&str],
+    ) {
+        let options = MarkdownOptions {
+            sections,
+            ..MarkdownOptions::default()
+        };
+
+        let document = process_md_file("+++\n+++\n# B\n\n# A", &options)
+            .expect("Failed to parse markdown input");
+
+        let result: Vec<_> = document
+            .sections
+            .iter()
+            .map(|section| section.id.as_str())
+            .collect();
+        assert_eq!(result, ids);
+    }
+
     #[rstest]
     fn process_md_file_errors_on_a_file_with_an_invalid_frontmatter(
         yaml_test: &str,
     ) {
-        let result = process_md_file(yaml_test, false);
+        let result = process_md_file(yaml_test, &MarkdownOptions::default());
         assert!(result.is_err());
     }
 
@@ -107,7 +367,7 @@ mod tests {
     fn process_md_file_errors_with_empty_input() {
         let input = "";
 
-        let result = process_md_file(input, false);
+        let result = process_md_file(input, &MarkdownOptions::default());
         assert!(result.is_err());
     }
 
@@ -115,7 +375,7 @@ mod tests {
     fn process_md_file_errors_when_frontmatter_is_not_present() {
         let input = "# This is a heading\n\nThis is a paragraph";
 
-        let result = process_md_file(input, false);
+        let result = process_md_file(input, &MarkdownOptions::default());
         assert!(result.is_err());
     }
 }
@@ -0,0 +1,1404 @@
+use super::{
+    BLOCKS_START, EXTERNAL_LINK, INLINES_START, MarkdownHeading,
+    MarkdownOptions, SCOPE_END, UNTRUSTED_END, UNTRUSTED_START, highlight_code,
+    is_flow_content, is_sanitizer_marker, merge_attribute, render_diagram,
+    render_math, slugify, trusted_html_node,
+};
+use markdown::{
+    mdast::{
+        AlignKind, AttributeContent, AttributeValue, Code, Definition,
+        FootnoteDefinition, Heading, Html, List, ListItem, Node, Root, Table,
+    },
+    unist::Position,
+};
+use serde::Serialize;
+use std::{collections::HashMap, fmt::Write};
+
+pub(super) const SAFE_HREF_PROTOCOLS: [&str; 6] =
+    ["http", "https", "irc", "ircs", "mailto", "xmpp"];
+const SAFE_SRC_PROTOCOLS: [&str; 2] = ["http", "https"];
+const FILTERED_TAGS: [&str; 9] = [
+    "iframe",
+    "noembed",
+    "noframes",
+    "plaintext",
+    "script",
+    "style",
+    "textarea",
+    "title",
+    "xmp",
+];
+
+/// Compiles the Markdown ast rooted at `root` into HTML.
+///
+/// Headings get the ids of the matching entries in `headings`, which should come from
+/// [`collect_md_headings`](super::collect_md_headings) on the same ast.
+#[must_use]
+pub fn render_md_html(
+    root: &Node,
+    headings: &[MarkdownHeading],
+    options: &MarkdownOptions,
+) -> String {
+    let (mut output, footnotes) =
+        render_md_html_and_footnotes(root, headings, options);
+    footnotes.append_to(&mut output);
+
+    output
+}
+
+/// Compiles the Markdown ast rooted at `root` into HTML, like [`render_md_html`], but returns
+/// its footnotes separately instead of appending their section to the output.
+#[must_use]
+pub fn render_md_html_and_footnotes(
+    root: &Node,
+    headings: &[MarkdownHeading],
+    options: &MarkdownOptions,
+) -> (String, HtmlFootnotes) {
+    render_scoped(root, headings, options, &FootnoteScope::default())
+}
+
+/// How the footnotes of a part of a document, such as a slide, are told apart from the footnotes
+/// of its other parts once they're placed on the same page.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(super) struct FootnoteScope {
+    /// Prefix of the footnotes' ids.
+    pub prefix: String,
+    /// Number of the footnotes before the part, which its own are numbered after.
+    pub offset: usize,
+}
+
+/// Compiles the Markdown ast rooted at `root` into HTML, like [`render_md_html_and_footnotes`],
+/// numbering and identifying its footnotes within `scope`.
+pub(super) fn render_scoped(
+    root: &Node,
+    headings: &[MarkdownHeading],
+    options: &MarkdownOptions,
+    scope: &FootnoteScope,
+) -> (String, HtmlFootnotes) {
+    let mut renderer = HtmlRenderer::new(root, headings, options, scope);
+    renderer.block(root, false);
+    if ends_with_line_ending(root) {
+        renderer.output.push('\n');
+    }
+    let output = std::mem::take(&mut renderer.output);
+    let output = renderer.sanitized(&output);
+    let section = renderer.footnote_section();
+
+    (
+        output,
+        HtmlFootnotes {
+            section,
+            notes: renderer.footnotes,
+        },
+    )
+}
+
+/// Compiles `blocks` into HTML, with the link and footnote `definitions` of their document, and
+/// returns their footnotes, numbered and identified within `scope`, separately.
+pub(super) fn render_blocks(
+    blocks: Vec<Node>,
+    definitions: &[Node],
+    headings: &[MarkdownHeading],
+    options: &MarkdownOptions,
+    scope: &FootnoteScope,
+) -> (String, HtmlFootnotes) {
+    let mut children = definitions.to_vec();
+    children.extend(blocks);
+    let root = Node::Root(Root {
+        children,
+        position: None,
+    });
+
+    render_scoped(&root, headings, options, scope)
+}
+
+/// The footnotes of a compiled Markdown document.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HtmlFootnotes {
+    /// HTML of the section listing every footnote, or an empty string if there are none.
+    pub section: String,
+    /// Footnotes in the order they're first referenced.
+    pub notes: Vec<Footnote>,
+}
+
+impl HtmlFootnotes {
+    /// Appends the footnote section to the compiled HTML of the document's contents.
+    pub fn append_to(&self, output: &mut String) {
+        if !self.section.is_empty() {
+            if !matches!(output.chars().last(), None | Some('\n' | '\r')) {
+                output.push('\n');
+            }
+            output.push_str(&self.section);
+        }
+    }
+}
+
+/// A footnote of a compiled Markdown document.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Footnote {
+    /// Number of the footnote, as shown in its references.
+    pub number: usize,
+    /// Id of the footnote, which its references link to.
+    pub id: String,
+    /// Id of the first reference to the footnote.
+    pub reference_id: String,
+    /// Compiled HTML of the footnote's contents.
+    pub content: String,
+}
+
+struct HtmlRenderer<'a> {
+    options: &'a MarkdownOptions,
+    scope: &'a FootnoteScope,
+    heading_ids: HashMap<usize, &'a str>,
+    definitions: HashMap<&'a str, &'a Definition>,
+    footnote_definitions: HashMap<&'a str, &'a FootnoteDefinition>,
+    footnote_calls: Vec<(&'a str, usize)>,
+    footnotes: Vec<Footnote>,
+    pending_checkbox: Option<bool>,
+    output: String,
+}
+
+impl<'a> HtmlRenderer<'a> {
+    fn new(
+        root: &'a Node,
+        headings: &'a [MarkdownHeading],
+        options: &'a MarkdownOptions,
+        scope: &'a FootnoteScope,
+    ) -> Self {
+        let mut renderer = Self {
+            options,
+            scope,
+            heading_ids: headings
+                .iter()
+                .filter_map(|heading| {
+                    Some((heading.offset?, heading.id.as_str()))
+                })
+                .collect(),
+            definitions: HashMap::new(),
+            footnote_definitions: HashMap::new(),
+            footnote_calls: Vec::new(),
+            footnotes: Vec::new(),
+            pending_checkbox: None,
+            output: String::new(),
+        };
+        renderer.collect_definitions(root);
+
+        renderer
+    }
+
+    fn collect_definitions(&mut self, node: &'a Node) {
+        match node {
+            Node::Definition(definition) => {
+                self.definitions
+                    .entry(&definition.identifier)
+                    .or_insert(definition);
+            }
+            Node::FootnoteDefinition(definition) => {
+                self.footnote_definitions
+                    .entry(&definition.identifier)
+                    .or_insert(definition);
+            }
+            _ => {}
+        }
+
+        for child in node.children().into_iter().flatten() {
+            self.collect_definitions(child);
+        }
+    }
+
+    fn push(&mut self, value: &str) {
+        if self.options.sanitizer.is_some() {
+            // The input's own marker characters are escaped, so they can't delimit inline HTML.
+            for c in value.chars() {
+                if is_sanitizer_marker(c) {
+                    let _ = write!(self.output, "&#x{:X};", u32::from(c));
+                } else {
+                    self.output.push(c);
+                }
+            }
+        } else {
+            self.output.push_str(value);
+        }
+    }
+
+    /// Pushes inline HTML from the input, to be sanitized once the whole output is rendered.
+    fn push_untrusted(&mut self, html: &str) {
+        self.output.push(UNTRUSTED_START);
+        self.push(html);
+        self.output.push(UNTRUSTED_END);
+    }
+
+    /// Sanitizes the inline HTML pushed to `output`, if there's a sanitizer.
+    fn sanitized(&self, output: &str) -> String {
+        self.options.sanitizer.as_ref().map_or_else(
+            || output.to_string(),
+            |sanitizer| sanitizer.sanitize_untrusted(output),
+        )
+    }
+
+    fn line_ending_if_needed(&mut self) {
+        let output = self.output.trim_end_matches(is_sanitizer_marker);
+        if !matches!(output.chars().last(), None | Some('\n' | '\r')) {
+            self.output.push('\n');
+        }
+    }
+
+    fn blocks(&mut self, children: &'a [Node], tight: bool) {
+        self.scope_marker(BLOCKS_START);
+        for child in children {
+            self.block(child, tight);
+        }
+        self.scope_marker(SCOPE_END);
+    }
+
+    fn inlines(&mut self, children: &'a [Node]) {
+        self.scope_marker(INLINES_START);
+        for child in children {
+            self.inline(child);
+        }
+        self.scope_marker(SCOPE_END);
+    }
+
+    /// Delimits the elements that inline HTML can leave open, if there's a sanitizer.
+    fn scope_marker(&mut self, marker: char) {
+        if self.options.sanitizer.is_some() {
+            self.output.push(marker);
+        }
+    }
+
+    fn block(&mut self, node: &'a Node, tight: bool) {
+        match node {
+            Node::Root(root) => self.blocks(&root.children, tight),
+            Node::Paragraph(paragraph) => {
+                if !tight {
+                    self.line_ending_if_needed();
+                    self.open_tag("p");
+                }
+                self.checkbox();
+                self.inlines(&paragraph.children);
+                if !tight {
+                    self.push("</p>");
+                }
+            }
+            Node::Heading(heading) => self.heading(heading),
+            Node::ThematicBreak(_) => {
+                self.line_ending_if_needed();
+                self.tag_start("hr", &["hr"], Vec::new());
+                self.push(" />");
+            }
+            Node::Blockquote(blockquote) => {
+                self.line_ending_if_needed();
+                self.open_tag("blockquote");
+                self.blocks(&blockquote.children, false);
+                self.line_ending_if_needed();
+                self.push("</blockquote>");
+            }
+            Node::List(list) => self.list(list),
+            Node::ListItem(item) => self.list_item(item, tight),
+            Node::Code(code) => {
+                self.line_ending_if_needed();
+                if let Some(html) = self.math_code(code) {
+                    self.push(&html);
+                    return;
+                }
+                if let Some(html) = self.diagram(code) {
+                    self.push(&html);
+                    return;
+                }
+                if let Some(html) = self.highlight(code) {
+                    self.push(&html);
+                    return;
+                }
+                self.open_tag("pre");
+                let class =
+                    code.lang.as_ref().map(|lang| format!("language-{lang}"));
+                let attributes: Vec<_> = class
+                    .iter()
+                    .map(|class| ("class", class.as_str()))
+                    .collect();
+                self.open_tag_with("code", &attributes);
+                self.code_value(&code.value, code.position.as_ref());
+                self.push("</code></pre>");
+            }
+            Node::Math(math) => {
+                self.line_ending_if_needed();
+                if let Some(html) = render_math(&math.value, true) {
+                    self.push(&html);
+                    return;
+                }
+                self.open_tag("pre");
+                self.open_tag_with(
+                    "code",
+                    &[("class", "language-math math-display")],
+                );
+                self.code_value(&math.value, math.position.as_ref());
+                self.push("</code></pre>");
+            }
+            Node::Table(table) => self.table(table),
+            Node::Html(html) => {
+                self.line_ending_if_needed();
+                self.html(html);
+            }
+            Node::Definition(_)
+            | Node::FootnoteDefinition(_)
+            | Node::Toml(_)
+            | Node::Yaml(_)
+            | Node::MdxjsEsm(_) => {}
+            Node::MdxFlowExpression(expression) => {
+                self.expression(&expression.value, true);
+            }
+            Node::MdxJsxFlowElement(element) => {
+                self.line_ending_if_needed();
+                self.element_start(
+                    element.name.as_deref(),
+                    &element.attributes,
+                );
+                if element.children.iter().any(is_flow_content) {
+                    self.blocks(&element.children, false);
+                    self.line_ending_if_needed();
+                } else {
+                    self.inlines(&element.children);
+                }
+                self.element_end(element.name.as_deref());
+            }
+            // Inline content among blocks, such as the image of a figure.
+            _ => {
+                self.line_ending_if_needed();
+                self.inline(node);
+            }
+        }
+    }
+
+    fn heading(&mut self, heading: &'a Heading) {
+        let id = heading
+            .position
+            .as_ref()
+            .and_then(|p| self.heading_ids.get(&p.start.offset))
+            .map_or_else(
+                || slugify(&children_text(&heading.children)),
+                ToString::to_string,
+            );
+        let id = encode(&id);
+
+        self.line_ending_if_needed();
+        let tag = format!("h{}", heading.depth);
+        self.tag_start(&tag, &[&tag], vec![("id".to_string(), id.clone())]);
+        self.push(">");
+        self.inlines(&heading.children);
+        if self.options.heading_anchors {
+            self.push(&format!(
+                " <a class=\"anchor\" href=\"#{id}\" aria-hidden=\"true\">#</a>"
+            ));
+        }
+        self.push(&format!("</{tag}>"));
+    }
+
+    fn list(&mut self, list: &'a List) {
+        let loose = list.spread
+            || list.children.iter().any(
+                |item| matches!(item, Node::ListItem(item) if item.spread),
+            );
+
+        self.line_ending_if_needed();
+        let tag = if list.ordered { "ol" } else { "ul" };
+        let attributes = list
+            .start
+            .filter(|start| list.ordered && *start != 1)
+            .map(|start| ("start".to_string(), start.to_string()))
+            .into_iter()
+            .collect();
+        self.tag_start(tag, &[tag], attributes);
+        self.push(">");
+        self.blocks(&list.children, !loose);
+        self.push(if list.ordered { "\n</ol>" } else { "\n</ul>" });
+    }
+
+    fn list_item(&mut self, item: &'a ListItem, tight: bool) {
+        self.line_ending_if_needed();
+        self.open_tag("li");
+        self.pending_checkbox = item.checked;
+        if item.children.is_empty() {
+            self.checkbox();
+        }
+        self.blocks(&item.children, tight);
+
+        let tight_paragraph =
+            tight && matches!(item.children.last(), Some(Node::Paragraph(_)));
+        if !(item.children.is_empty() || tight_paragraph) {
+            self.line_ending_if_needed();
+        }
+        self.push("</li>");
+    }
+
+    fn table(&mut self, table: &'a Table) {
+        self.line_ending_if_needed();
+        if let Some(class) = &self.options.hooks.table_wrapper {
+            self.push(&format!("<div class=\"{}\">\n", encode(class)));
+        }
+        self.open_tag("table");
+        if let Some((head, body)) = table.children.split_first() {
+            self.line_ending_if_needed();
+            self.open_tag("thead");
+            self.table_row(head, &table.align, "th");
+            self.line_ending_if_needed();
+            self.push("</thead>");
+
+            if !body.is_empty() {
+                self.line_ending_if_needed();
+                self.open_tag("tbody");
+                for row in body {
+                    self.table_row(row, &table.align, "td");
+                }
+                self.line_ending_if_needed();
+                self.push("</tbody>");
+            }
+        }
+        self.line_ending_if_needed();
+        self.push("</table>");
+        if self.options.hooks.table_wrapper.is_some() {
+            self.push("\n</div>");
+        }
+    }
+
+    fn math_code(&self, code: &Code) -> Option<String> {
+        if self.options.math && code.lang.as_deref() == Some("math") {
+            render_math(&code.value, true)
+        } else {
+            None
+        }
+    }
+
+    fn diagram(&self, code: &Code) -> Option<String> {
+        if self.options.diagrams
+            && matches!(code.lang.as_deref(), Some("bob" | "svgbob"))
+        {
+            render_diagram(&code.value)
+        } else {
+            None
+        }
+    }
+
+    fn highlight(&self, code: &Code) -> Option<String> {
+        highlight_code(
+            &code.value,
+            code.lang.as_deref()?,
+            self.options.code_highlighting.as_ref()?,
+            &self.options.hooks.attributes(&["pre"]),
+            &self.options.hooks.attributes(&["code"]),
+        )
+    }
+
+    fn checkbox(&mut self) {
+        if let Some(checked) = self.pending_checkbox.take() {
+            self.push("<input type=\"checkbox\" disabled=\"\" ");
+            if checked {
+                self.push("checked=\"\" ");
+            }
+            self.push("/> ");
+        }
+    }
+
+    /// Pushes an MDX expression as the text it was written as, since expressions aren't evaluated,
+    /// in its own paragraph if it's a block.
+    fn expression(&mut self, value: &str, flow: bool) {
+        if flow {
+            self.line_ending_if_needed();
+            self.open_tag("p");
+        }
+        self.push(&encode(&format!("{{{value}}}")));
+        if flow {
+            self.push("</p>");
+        }
+    }
+
+    fn code_value(&mut self, value: &str, position: Option<&Position>) {
+        self.push(&encode(value));
+        // The value of a block holding a single blank line is empty, like the one of an empty
+        // block, so the lines between its fences tell them apart.
+        let blank_line = position.is_some_and(|position| {
+            position.end.line > position.start.line + 1
+        });
+        if !value.is_empty() || blank_line {
+            self.push("\n");
+        }
+    }
+
+    fn table_row(&mut self, row: &'a Node, align: &[AlignKind], tag: &str) {
+        let cells = row.children().map_or(&[][..], Vec::as_slice);
+
+        self.line_ending_if_needed();
+        self.open_tag("tr");
+        for (column, align) in align.iter().enumerate() {
+            self.line_ending_if_needed();
+            let align = match align {
+                AlignKind::Left => Some("left"),
+                AlignKind::Right => Some("right"),
+                AlignKind::Center => Some("center"),
+                AlignKind::None => None,
+            };
+            let attributes: Vec<_> =
+                align.iter().map(|align| ("align", *align)).collect();
+            self.open_tag_with(tag, &attributes);
+            if let Some(cell) = cells.get(column) {
+                self.inlines(cell.children().map_or(&[][..], Vec::as_slice));
+            }
+            self.push(&format!("</{tag}>"));
+        }
+        self.line_ending_if_needed();
+        self.push("</tr>");
+    }
+
+    // Element nodes are never parsed from the input, so they're created by jango itself.
+    fn element_start(
+        &mut self,
+        name: Option<&str>,
+        attributes: &[AttributeContent],
+    ) {
+        let Some(name) = name else {
+            return;
+        };
+
+        let attributes: Vec<_> = attributes
+            .iter()
+            .filter_map(|attribute| match attribute {
+                AttributeContent::Property(property) => Some((
+                    property.name.as_str(),
+                    match &property.value {
+                        Some(AttributeValue::Literal(value)) => value.as_str(),
+                        _ => "",
+                    },
+                )),
+                AttributeContent::Expression(_) => None,
+            })
+            .collect();
+        self.open_tag_with(name, &attributes);
+    }
+
+    fn element_end(&mut self, name: Option<&str>) {
+        if let Some(name) = name {
+            self.push(&format!("</{name}>"));
+        }
+    }
+
+    fn html(&mut self, html: &Html) {
+        if self.options.sanitizer.is_some() {
+            self.push_untrusted(&html.value);
+        } else if self.options.inline_html {
+            self.push(&filter_tags(&html.value));
+        } else {
+            self.push(&encode(&html.value));
+        }
+    }
+
+    fn inline(&mut self, node: &'a Node) {
+        match node {
+            Node::Text(text) => self.push(&encode(&text.value)),
+            Node::Emphasis(emphasis) => {
+                self.open_tag("em");
+                self.inlines(&emphasis.children);
+                self.push("</em>");
+            }
+            Node::Strong(strong) => {
+                self.open_tag("strong");
+                self.inlines(&strong.children);
+                self.push("</strong>");
+            }
+            Node::Delete(delete) => {
+                self.open_tag("del");
+                self.inlines(&delete.children);
+                self.push("</del>");
+            }
+            Node::InlineCode(code) => {
+                self.open_tag("code");
+                self.push(&format!("{}</code>", encode(&code.value)));
+            }
+            Node::InlineMath(math) => {
+                if let Some(html) = render_math(&math.value, false) {
+                    self.push(&html);
+                    return;
+                }
+                self.open_tag_with(
+                    "code",
+                    &[("class", "language-math math-inline")],
+                );
+                self.push(&format!("{}</code>", encode(&math.value)));
+            }
+            Node::Break(_) => self.push("<br />\n"),
+            Node::Html(html) => self.html(html),
+            Node::Link(link) => {
+                self.link(&link.url, link.title.as_deref(), &link.children);
+            }
+            Node::LinkReference(reference) => {
+                match self.definitions.get(reference.identifier.as_str()) {
+                    Some(definition) => self.link(
+                        &definition.url,
+                        definition.title.as_deref(),
+                        &reference.children,
+                    ),
+                    None => self.inlines(&reference.children),
+                }
+            }
+            Node::Image(image) => {
+                self.image(&image.url, &image.alt, image.title.as_deref());
+            }
+            Node::ImageReference(reference) => {
+                if let Some(definition) =
+                    self.definitions.get(reference.identifier.as_str())
+                {
+                    self.image(
+                        &definition.url,
+                        &reference.alt,
+                        definition.title.as_deref(),
+                    );
+                }
+            }
+            Node::FootnoteReference(reference) => {
+                self.footnote_reference(&reference.identifier);
+            }
+            Node::MdxJsxTextElement(element) => {
+                if let Some(html) = trusted_html_node(node) {
+                    self.push(&html.value);
+                    return;
+                }
+                self.element_start(
+                    element.name.as_deref(),
+                    &element.attributes,
+                );
+                self.inlines(&element.children);
+                self.element_end(element.name.as_deref());
+            }
+            Node::MdxTextExpression(expression) => {
+                self.expression(&expression.value, false);
+            }
+            _ => self.block(node, false),
+        }
+    }
+
+    fn link(&mut self, url: &str, title: Option<&str>, children: &'a [Node]) {
+        let mut attributes =
+            vec![("href".to_string(), sanitize_url(url, &SAFE_HREF_PROTOCOLS))];
+        attributes
+            .extend(title.map(|title| ("title".to_string(), encode(title))));
+        let elements: &[&str] = if is_external(url) {
+            &["a", EXTERNAL_LINK]
+        } else {
+            &["a"]
+        };
+        self.tag_start("a", elements, attributes);
+        self.push(">");
+        self.inlines(children);
+        self.push("</a>");
+    }
+
+    fn image(&mut self, url: &str, alt: &str, title: Option<&str>) {
+        let mut attributes = vec![
+            ("src".to_string(), sanitize_url(url, &SAFE_SRC_PROTOCOLS)),
+            ("alt".to_string(), encode(alt)),
+        ];
+        attributes
+            .extend(title.map(|title| ("title".to_string(), encode(title))));
+        self.tag_start("img", &["img"], attributes);
+        self.push(" />");
+    }
+
+    fn open_tag(&mut self, tag: &str) {
+        self.open_tag_with(tag, &[]);
+    }
+
+    /// Pushes the opening tag of `tag` with `attributes`, merged with the extra ones configured
+    /// for it.
+    fn open_tag_with(&mut self, tag: &str, attributes: &[(&str, &str)]) {
+        let attributes = attributes
+            .iter()
+            .map(|(name, value)| ((*name).to_string(), encode(value)))
+            .collect();
+        self.tag_start(tag, &[tag], attributes);
+        self.push(">");
+    }
+
+    /// Pushes the opening tag of `tag` without its closing `>`, with `attributes`, whose values
+    /// are already encoded, merged with the extra ones configured for `elements`.
+    ///
+    /// Configured attributes replace the ones of `attributes`, except for a generated `id`, which
+    /// anchors, tables of contents and cross-references link to.
+    fn tag_start(
+        &mut self,
+        tag: &str,
+        elements: &[&str],
+        mut attributes: Vec<(String, String)>,
+    ) {
+        let has_id = attributes
+            .iter()
+            .any(|(name, _)| name.eq_ignore_ascii_case("id"));
+        for (name, value) in self.options.hooks.attributes(elements) {
+            if !(has_id && name.eq_ignore_ascii_case("id")) {
+                merge_attribute(&mut attributes, &name, &encode(&value));
+            }
+        }
+
+        self.push(&format!("<{tag}"));
+        for (name, value) in attributes {
+            self.push(&format!(" {name}=\"{value}\""));
+        }
+    }
+
+    fn footnote_reference(&mut self, identifier: &'a str) {
+        let index = self
+            .footnote_calls
+            .iter()
+            .position(|(id, _)| *id == identifier)
+            .unwrap_or_else(|| {
+                self.footnote_calls.push((identifier, 0));
+                self.footnote_calls.len() - 1
+            });
+        self.footnote_calls[index].1 += 1;
+
+        let count = self.footnote_calls[index].1;
+        let id = encode(&self.footnote_id(identifier));
+        let prefix = encode(&self.scope.prefix);
+        let suffix = if count > 1 {
+            format!("-{count}")
+        } else {
+            String::new()
+        };
+
+        self.open_tag("sup");
+        self.push(&format!(
+            "<a href=\"#user-content-fn-{id}\" \
+            id=\"user-content-fnref-{id}{suffix}\" data-footnote-ref=\"\" \
+            aria-describedby=\"{prefix}footnote-label\">{}</a></sup>",
+            self.scope.offset + index + 1
+        ));
+    }
+
+    fn footnote_section(&mut self) -> String {
+        if self.footnote_calls.is_empty() {
+            return String::new();
+        }
+
+        let outer = std::mem::take(&mut self.output);
+        self.push(&format!(
+            "<section data-footnotes=\"\" class=\"footnotes\">\
+            <h2 id=\"{}footnote-label\" class=\"sr-only\">Footnotes</h2>\n{}",
+            encode(&self.scope.prefix),
+            if self.scope.offset == 0 {
+                "<ol>".to_string()
+            } else {
+                format!("<ol start=\"{}\">", self.scope.offset + 1)
+            }
+        ));
+
+        // Footnotes can reference other footnotes, so calls may grow while rendering.
+        let mut index = 0;
+        while index < self.footnote_calls.len() {
+            self.footnote_item(index);
+            index += 1;
+        }
+
+        self.push("\n</ol>\n</section>\n");
+
+        std::mem::replace(&mut self.output, outer)
+    }
+
+    fn footnote_item(&mut self, index: usize) {
+        let (identifier, count) = self.footnote_calls[index];
+        let id = self.footnote_id(identifier);
+
+        let backreferences = (1..=count)
+            .map(|call| {
+                let (suffix, label) = if call > 1 {
+                    (format!("-{call}"), format!("<sup>{call}</sup>"))
+                } else {
+                    (String::new(), String::new())
+                };
+                format!(
+                    "<a href=\"#user-content-fnref-{}{suffix}\" \
+                    data-footnote-backref=\"\" aria-label=\"Back to content\" \
+                    class=\"data-footnote-backref\">↩{label}</a>",
+                    encode(&id)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        let content = match self.footnote_definitions.get(identifier) {
+            Some(definition) => {
+                let outer = std::mem::take(&mut self.output);
+                self.blocks(&definition.children, false);
+                let content = std::mem::replace(&mut self.output, outer);
+                self.sanitized(&content)
+            }
+            None => String::new(),
+        };
+
+        self.footnotes.push(Footnote {
+            number: self.scope.offset + index + 1,
+            id: format!("user-content-fn-{id}"),
+            reference_id: format!("user-content-fnref-{id}"),
+            content: content.clone(),
+        });
+
+        self.push(&format!("\n<li id=\"user-content-fn-{}\">\n", encode(&id)));
+        if let Some(content) = content.strip_suffix("</p>") {
+            self.push(&format!("{content} {backreferences}</p>"));
+        } else {
+            self.push(&content);
+            self.line_ending_if_needed();
+            self.push(&backreferences);
+        }
+        self.line_ending_if_needed();
+        self.push("</li>");
+    }
+
+    fn footnote_id(&self, identifier: &str) -> String {
+        format!(
+            "{}{}",
+            self.scope.prefix,
+            markdown::sanitize(&identifier.to_lowercase())
+        )
+    }
+}
+
+fn ends_with_line_ending(root: &Node) -> bool {
+    let children = root.children().map_or(&[][..], Vec::as_slice);
+    let Some(last_rendered) = children.iter().rposition(|child| {
+        !matches!(child, Node::Definition(_) | Node::FootnoteDefinition(_))
+    }) else {
+        return false;
+    };
+
+    // Definitions after the last rendered block leave their line ending behind.
+    if last_rendered + 1 < children.len() {
+        return true;
+    }
+
+    let end = root.position().map(|p| p.end.offset);
+    let last_end = children[last_rendered].position().map(|p| p.end.offset);
+    matches!((end, last_end), (Some(end), Some(last)) if end > last)
+}
+
+fn is_external(url: &str) -> bool {
+    ["http://", "https://", "//"]
+        .iter()
+        .any(|prefix| url.starts_with(prefix))
+}
+
+fn children_text(children: &[Node]) -> String {
+    children.iter().map(ToString::to_string).collect()
+}
+
+/// Encodes the characters of `value` that have a special meaning in HTML.
+#[must_use]
+pub fn encode(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => result.push_str("&amp;"),
+            '"' => result.push_str("&quot;"),
+            '<' => result.push_str("&lt;"),
+            '>' => result.push_str("&gt;"),
+            '\0' => result.push('\u{FFFD}'),
+            _ => result.push(c),
+        }
+    }
+
+    result
+}
+
+/// Makes `url` safe to use as an attribute value, dropping it if it uses a protocol that isn't
+/// in `protocols`.
+pub(super) fn sanitize_url(url: &str, protocols: &[&str]) -> String {
+    let url = markdown::sanitize(url);
+
+    let end = url.find(['?', '#', '/']).unwrap_or(url.len());
+    match url[..end].find(':') {
+        Some(colon)
+            if !protocols.contains(&url[..colon].to_lowercase().as_str()) =>
+        {
+            String::new()
+        }
+        _ => url,
+    }
+}
+
+/// Escapes the opening of the tags that GFM disallows in raw HTML.
+fn filter_tags(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    let mut rest = value;
+
+    while let Some(index) = rest.find('<') {
+        let (before, after) = rest.split_at(index);
+        result.push_str(before);
+
+        let name_start = usize::from(after[1..].starts_with('/')) + 1;
+        let name: String = after[name_start..]
+            .chars()
+            .take_while(char::is_ascii_alphabetic)
+            .collect();
+        let terminated = after[name_start + name.len()..]
+            .chars()
+            .next()
+            .is_none_or(|c| {
+                matches!(c, '\t' | '\n' | '\x0C' | '\r' | ' ' | '/' | '>')
+            });
+
+        if terminated
+            && FILTERED_TAGS.contains(&name.to_ascii_lowercase().as_str())
+        {
+            result.push_str("&lt;");
+        } else {
+            result.push('<');
+        }
+        rest = &after[1..];
+    }
+    result.push_str(rest);
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::templates::{
+        CodeHighlighting, Figures, HOOK_ELEMENTS, HtmlSanitizer,
+        collect_md_headings, parse_md, parse_md_content, trusted_html,
+    };
+    use rstest::*;
+
+    fn render(input: &str, options: &MarkdownOptions) -> String {
+        let mut tree = parse_md(input, options).expect("This should not fail");
+        let headings = collect_md_headings(&mut tree);
+
+        render_md_html(&tree, &headings, options)
+    }
+
+    #[rstest]
+    #[case(
+        "*a* **b** ~~c~~ `d`",
+        "<p><em>a</em> <strong>b</strong> <del>c</del> <code>d</code></p>"
+    )]
+    #[case("> quote", "<blockquote>\n<p>quote</p>\n</blockquote>")]
+    #[case("- a\n- b", "<ul>\n<li>a</li>\n<li>b</li>\n</ul>")]
+    #[case(
+        "- a\n\n- b",
+        "<ul>\n<li>\n<p>a</p>\n</li>\n<li>\n<p>b</p>\n</li>\n</ul>"
+    )]
+    #[case("3. a\n4. b", "<ol start=\"3\">\n<li>a</li>\n<li>b</li>\n</ol>")]
+    #[case(
+        "- [ ] a\n- [x] b",
+        "<ul>\n<li><input type=\"checkbox\" disabled=\"\" /> a</li>\n<li><input type=\"checkbox\" disabled=\"\" checked=\"\" /> b</li>\n</ul>"
+    )]
+    #[case(
+        "```rust\nlet x = 1;\n```",
+        "<pre><code class=\"language-rust\">let x = 1;\n</code></pre>"
+    )]
+    #[case("a  \nb", "<p>a<br />\nb</p>")]
+    #[case("***", "<hr />")]
+    #[case(
+        "[a](https://example.com \"T\")",
+        "<p><a href=\"https://example.com\" title=\"T\">a</a></p>"
+    )]
+    #[case("[a](javascript:alert(1))", "<p><a href=\"\">a</a></p>")]
+    #[case("![a *b*](i.png)", "<p><img src=\"i.png\" alt=\"a b\" /></p>")]
+    #[case("[a][x]\n\n[x]: /url", "<p><a href=\"/url\">a</a></p>\n")]
+    #[case("```\n```", "<pre><code></code></pre>")]
+    #[case("```\n\n```", "<pre><code>\n</code></pre>")]
+    #[case(
+        "> ```\n>\n> ```",
+        "<blockquote>\n<pre><code>\n</code></pre>\n</blockquote>"
+    )]
+    #[case(
+        "- a\n\n  ```\n\n  ```\n- b",
+        "<ul>\n<li>\n<p>a</p>\n<pre><code>\n</code></pre>\n</li>\n<li>\n<p>b</p>\n</li>\n</ul>"
+    )]
+    #[case(
+        "![a ![b](c.png) d](e.png)",
+        "<p><img src=\"e.png\" alt=\"a b d\" /></p>"
+    )]
+    #[case(
+        "[![a ![b \"c\"](c.png)](d.png)](e)",
+        "<p><a href=\"e\"><img src=\"d.png\" alt=\"a b &quot;c&quot;\" /></a></p>"
+    )]
+    #[case(
+        "![a ![b *c*][r] d][r]\n\n[r]: x.png",
+        "<p><img src=\"x.png\" alt=\"a b c d\" /></p>\n"
+    )]
+    #[case("![a  \nb](c.png)", "<p><img src=\"c.png\" alt=\"a\nb\" /></p>")]
+    #[case(
+        "| a | b |\n| :- | -: |\n| 1 |",
+        "<table>\n<thead>\n<tr>\n<th align=\"left\">a</th>\n<th align=\"right\">b</th>\n</tr>\n</thead>\n<tbody>\n<tr>\n<td align=\"left\">1</td>\n<td align=\"right\"></td>\n</tr>\n</tbody>\n</table>"
+    )]
+    #[case("<b>x</b> & y", "<p>&lt;b&gt;x&lt;/b&gt; &amp; y</p>")]
+    fn render_matches_markdown_html(
+        #[case] input: &str,
+        #[case] expected: &str,
+    ) {
+        let options = MarkdownOptions::default();
+
+        assert_eq!(render(input, &options), expected);
+        assert_eq!(
+            markdown::to_html_with_options(input, &markdown::Options::gfm())
+                .expect("This should not fail"),
+            expected
+        );
+    }
+
+    #[test]
+    fn render_adds_ids_to_headings() {
+        let result = render(
+            "# Intro\n\n## Intro {#custom}\n\n## Intro",
+            &MarkdownOptions::default(),
+        );
+
+        assert_eq!(
+            result,
+            "<h1 id=\"intro\">Intro</h1>\n<h2 id=\"custom\">Intro</h2>\n\
+            <h2 id=\"intro-1\">Intro</h2>"
+        );
+    }
+
+    #[test]
+    fn render_adds_anchors_to_headings() {
+        let options = MarkdownOptions {
+            heading_anchors: true,
+            ..MarkdownOptions::default()
+        };
+
+        assert_eq!(
+            render("## Hello *world*", &options),
+            "<h2 id=\"hello-world\">Hello <em>world</em> <a class=\"anchor\" \
+            href=\"#hello-world\" aria-hidden=\"true\">#</a></h2>"
+        );
+    }
+
+    #[test]
+    fn render_highlights_code_blocks() {
+        let options = MarkdownOptions {
+            code_highlighting: Some(CodeHighlighting::Classes),
+            ..MarkdownOptions::default()
+        };
+
+        let result =
+            render("```rust\nlet x = 1;\n```\n\n```\nplain\n```", &options);
+        assert!(result.starts_with(
+            "<pre class=\"code\"><code class=\"language-rust\"><span class=\"source rust\">"
+        ));
+        assert!(result.ends_with("\n<pre><code>plain\n</code></pre>"));
+    }
+
+    #[test]
+    fn render_converts_math_to_mathml() {
+        let options = MarkdownOptions {
+            math: true,
+            ..MarkdownOptions::default()
+        };
+
+        let result = render(
+            "Inline $x^2$.\n\n$$\n\\sqrt{y}\n$$\n\n```math\nz\n```",
+            &options,
+        );
+        assert!(result.starts_with("<p>Inline <math><msup>"));
+        assert_eq!(result.matches("display=\"block\"").count(), 2);
+        assert!(result.contains("<msqrt><mi>y</mi></msqrt>"));
+    }
+
+    #[test]
+    fn render_keeps_invalid_math_as_code() {
+        let options = MarkdownOptions {
+            math: true,
+            ..MarkdownOptions::default()
+        };
+
+        assert_eq!(
+            render("$\\frac{a$", &options),
+            "<p><code class=\"language-math math-inline\">\\frac{a</code></p>"
+        );
+    }
+
+    #[test]
+    fn render_ignores_math_when_disabled() {
+        assert_eq!(render("$x$", &MarkdownOptions::default()), "<p>$x$</p>");
+    }
+
+    #[test]
+    fn render_sanitizes_inline_html() {
+        let options = MarkdownOptions {
+            sanitizer: Some(HtmlSanitizer::default()),
+            ..MarkdownOptions::default()
+        };
+
+        assert_eq!(
+            render(
+                "<details>\n<summary>a</summary>\n\n<kbd>b</kbd> <img src=x onerror=alert(1)>\n\n</details>\n\n<script>\nalert(1)\n</script>",
+                &options
+            ),
+            "<details>\n<summary>a</summary>\n<p><kbd>b</kbd> <img src=\"x\"></p>\n</details>\n"
+        );
+    }
+
+    #[test]
+    fn render_only_sanitizes_inline_html() {
+        let mut options = MarkdownOptions {
+            sanitizer: Some(HtmlSanitizer::default()),
+            ..MarkdownOptions::default()
+        };
+        options
+            .hooks
+            .add_attribute("p", "style", "color: red")
+            .expect("Failed to add attribute");
+
+        assert_eq!(
+            render(
+                "<div onclick=\"alert(1)\">\n\n*a* <input> \u{E003}<b onclick=x>b</b>[^1]\n\n[^1]: <u>c</u><script>d</script>",
+                &options
+            ),
+            "<div>\n<p style=\"color: red\"><em>a</em>  &#xE003;<b>b</b><sup><a href=\"#user-content-fn-1\" \
+            id=\"user-content-fnref-1\" data-footnote-ref=\"\" \
+            aria-describedby=\"footnote-label\">1</a></sup></p>\n</div>\n\
+            <section data-footnotes=\"\" class=\"footnotes\">\
+            <h2 id=\"footnote-label\" class=\"sr-only\">Footnotes</h2>\n<ol>\n\
+            <li id=\"user-content-fn-1\">\n<p style=\"color: red\"><u>c</u> \
+            <a href=\"#user-content-fnref-1\" data-footnote-backref=\"\" \
+            aria-label=\"Back to content\" class=\"data-footnote-backref\">↩</a></p>\n\
+            </li>\n</ol>\n</section>\n"
+        );
+    }
+
+    #[test]
+    fn render_keeps_private_use_characters_when_sanitizing() {
+        let options = MarkdownOptions {
+            sanitizer: Some(HtmlSanitizer::default()),
+            ..MarkdownOptions::default()
+        };
+
+        assert_eq!(
+            render(
+                "\u{E002}a\u{E003} <kbd title=\"\u{E005}\">\u{E004}</kbd> `\u{E006}`",
+                &options
+            ),
+            "<p>&#xE002;a&#xE003; <kbd title=\"\u{E005}\">&#xE004;</kbd> \
+            <code>&#xE006;</code></p>"
+        );
+    }
+
+    #[test]
+    fn render_keeps_generated_html_out_of_inline_attributes() {
+        let options = MarkdownOptions {
+            sanitizer: Some(HtmlSanitizer::default()),
+            ..MarkdownOptions::default()
+        };
+
+        assert_eq!(
+            render(
+                "<div title=\"\n\n[a](onmouseover=alert(1)//)\n\n<div>\">",
+                &options
+            ),
+            "\n<p><a href=\"onmouseover=alert(1)//\">a</a></p>\n<div>\"&gt;\n</div>"
+        );
+    }
+
+    #[rstest]
+    #[case::script(
+        "Hello <script> world\n\n# Heading\n\nMore **text**",
+        "<p>Hello </p>\n<h1 id=\"heading\">Heading</h1>\n<p>More <strong>text</strong></p>"
+    )]
+    #[case::style("a <style>b *c*\n\nd", "<p>a </p>\n<p>d</p>")]
+    #[case::textarea(
+        "a <textarea>b</textarea> c <textarea>\n\nd",
+        "<p>a  c </p>\n<p>d</p>"
+    )]
+    #[case::iframe_block("<iframe src=x>\n\n*a*", "\n<p><em>a</em></p>")]
+    #[case::xmp_block("<xmp>\n\n*a*", "\n<p><em>a</em></p>")]
+    #[case::noscript("a <noscript>b\n\n*c*", "<p>a </p>\n<p><em>c</em></p>")]
+    #[case::link(
+        "a <a href=\"x\">b *c*\n\nd",
+        "<p>a <a href=\"x\">b <em>c</em></a></p>\n<p>d</p>"
+    )]
+    #[case::link_in_emphasis(
+        "*a <a href=\"x\">b* c",
+        "<p><em>a <a href=\"x\">b</a></em> c</p>"
+    )]
+    #[case::nested_inlines(
+        "<kbd>Ctrl</kbd>+<b>*a*</b>",
+        "<p><kbd>Ctrl</kbd>+<b><em>a</em></b></p>"
+    )]
+    #[case::container_in_blockquote(
+        "> <div>\n>\n> a\n\nb",
+        "<blockquote>\n<div>\n<p>a</p>\n</div>\n</blockquote>\n<p>b</p>"
+    )]
+    fn render_keeps_unclosed_inline_html_in_its_block(
+        #[case] input: &str,
+        #[case] expected: &str,
+    ) {
+        let options = MarkdownOptions {
+            sanitizer: Some(HtmlSanitizer::default()),
+            ..MarkdownOptions::default()
+        };
+
+        assert_eq!(render(input, &options), expected);
+    }
+
+    #[test]
+    fn render_adds_attributes_to_every_hook_element() {
+        let mut options = MarkdownOptions {
+            figures: Some(Figures::default()),
+            ..MarkdownOptions::default()
+        };
+        for element in HOOK_ELEMENTS {
+            options
+                .hooks
+                .add_attribute(element, "data-hook", element)
+                .expect("Failed to add attribute");
+        }
+
+        let result = parse_md_content(
+            "# 1\n\n## 2\n\n### 3\n\n#### 4\n\n##### 5\n\n###### 6\n\n\
+            *a* **b** ~~c~~ `d` [e](f.html) [g](https://example.com)[^1]\n\n\
+            > h\n\n---\n\n- i\n\n1. j\n\n```\nk\n```\n\n\
+            ![l](l.png \"L\")\n\n| m |\n| - |\n| n |\n\n[^1]: o",
+            &options,
+        )
+        .expect("Failed to parse markdown input");
+
+        for element in HOOK_ELEMENTS {
+            let tag = element.split(':').next().unwrap_or(element);
+            let attribute = result
+                .find(&format!(" data-hook=\"{element}\""))
+                .unwrap_or_else(|| panic!("`{element}` has no attributes"));
+            let start = result[..attribute].rfind('<').unwrap_or(0);
+            assert!(
+                result[start + 1..].starts_with(&format!("{tag} ")),
+                "`{element}` has no attributes in {result}"
+            );
+        }
+    }
+
+    #[test]
+    fn render_only_trusts_html_generated_by_jango() {
+        let root = Node::Root(markdown::mdast::Root {
+            children: vec![
+                Node::Html(Html {
+                    value: "<script>alert(1)</script>".to_string(),
+                    position: None,
+                }),
+                trusted_html("<div class=\"embed\"></div>".to_string()),
+            ],
+            position: None,
+        });
+
+        assert_eq!(
+            render_md_html(&root, &[], &MarkdownOptions::default()),
+            "&lt;script&gt;alert(1)&lt;/script&gt;\n<div class=\"embed\"></div>"
+        );
+    }
+
+    #[test]
+    fn render_adds_configured_attributes() {
+        let mut options = MarkdownOptions::default();
+        options
+            .hooks
+            .add_attribute("img", "loading", "lazy")
+            .expect("Failed to add attribute");
+        options
+            .hooks
+            .add_attribute("a:external", "rel", "noopener")
+            .expect("Failed to add attribute");
+        options
+            .hooks
+            .add_attribute("a:external", "target", "_blank")
+            .expect("Failed to add attribute");
+        options
+            .hooks
+            .add_attribute("H2", "class", "title")
+            .expect("Failed to add attribute");
+
+        assert_eq!(
+            render(
+                "## A\n\n[in](/a) [out](https://example.com) ![i](i.png)",
+                &options
+            ),
+            "<h2 id=\"a\" class=\"title\">A</h2>\n<p><a href=\"/a\">in</a> \
+            <a href=\"https://example.com\" rel=\"noopener\" target=\"_blank\">out</a> \
+            <img src=\"i.png\" alt=\"i\" loading=\"lazy\" /></p>"
+        );
+    }
+
+    #[test]
+    fn render_replaces_attributes_with_configured_ones() {
+        let mut options = MarkdownOptions::default();
+        for (element, name, value) in [
+            ("a", "href", "y & z"),
+            ("img", "alt", "decor"),
+            ("img", "title", "z"),
+            ("ol", "start", "7"),
+        ] {
+            options
+                .hooks
+                .add_attribute(element, name, value)
+                .expect("Failed to add attribute");
+        }
+
+        assert_eq!(
+            render("[a](x.html \"X\") ![i](i.png \"T\")\n\n3. b", &options),
+            "<p><a href=\"y &amp; z\" title=\"X\">a</a> \
+            <img src=\"i.png\" alt=\"decor\" title=\"z\" /></p>\n\
+            <ol start=\"7\">\n<li>b</li>\n</ol>"
+        );
+    }
+
+    #[test]
+    fn render_keeps_the_generated_ids_of_headings() {
+        let mut options = MarkdownOptions::default();
+        options.hooks.attributes.insert(
+            "h2".to_string(),
+            vec![("id".to_string(), "other".to_string())],
+        );
+
+        assert_eq!(render("## A", &options), "<h2 id=\"a\">A</h2>");
+    }
+
+    #[test]
+    fn render_wraps_tables() {
+        let mut options = MarkdownOptions::default();
+        options
+            .hooks
+            .add_attribute("table", "class", "table \"striped\"")
+            .expect("Failed to add attribute");
+        options.hooks.table_wrapper = Some("scroll".to_string());
+
+        assert_eq!(
+            render("| a |\n| - |\n| 1 |", &options),
+            "<div class=\"scroll\">\n<table class=\"table &quot;striped&quot;\">\n\
+            <thead>\n<tr>\n<th>a</th>\n</tr>\n</thead>\n<tbody>\n<tr>\n<td>1</td>\n\
+            </tr>\n</tbody>\n</table>\n</div>"
+        );
+    }
+
+    #[test]
+    fn render_filters_dangerous_inline_html() {
+        let options = MarkdownOptions {
+            inline_html: true,
+            ..MarkdownOptions::default()
+        };
+
+        assert_eq!(
+            render("<kbd>a</kbd> <script>b</script>", &options),
+            "<p><kbd>a</kbd> &lt;script>b&lt;/script></p>"
+        );
+    }
+
+    #[test]
+    fn render_adds_footnotes_at_the_end() {
+        let result =
+            render("A[^1] b[^1].\n\n[^1]: Note.", &MarkdownOptions::default());
+
+        assert_eq!(
+            result,
+            markdown::to_html_with_options(
+                "A[^1] b[^1].\n\n[^1]: Note.",
+                &markdown::Options::gfm()
+            )
+            .expect("This should not fail")
+        );
+    }
+}
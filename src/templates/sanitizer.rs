@@ -0,0 +1,566 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+const DEFAULT_TAGS: [&str; 60] = [
+    "a",
+    "abbr",
+    "b",
+    "bdi",
+    "bdo",
+    "blockquote",
+    "br",
+    "caption",
+    "cite",
+    "code",
+    "col",
+    "colgroup",
+    "dd",
+    "del",
+    "details",
+    "dfn",
+    "div",
+    "dl",
+    "dt",
+    "em",
+    "figcaption",
+    "figure",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "hr",
+    "i",
+    "img",
+    "ins",
+    "kbd",
+    "li",
+    "mark",
+    "ol",
+    "p",
+    "pre",
+    "q",
+    "rp",
+    "rt",
+    "ruby",
+    "s",
+    "samp",
+    "small",
+    "span",
+    "strong",
+    "sub",
+    "summary",
+    "sup",
+    "table",
+    "tbody",
+    "td",
+    "tfoot",
+    "th",
+    "thead",
+    "time",
+    "tr",
+    "u",
+    "ul",
+];
+const DEFAULT_GENERIC_ATTRIBUTES: [&str; 5] =
+    ["class", "dir", "id", "lang", "title"];
+const DEFAULT_TAG_ATTRIBUTES: [(&str, &[&str]); 13] = [
+    ("a", &["href"]),
+    ("blockquote", &["cite"]),
+    ("col", &["span"]),
+    ("colgroup", &["span"]),
+    ("del", &["cite", "datetime"]),
+    ("details", &["open"]),
+    ("img", &["alt", "height", "src", "width"]),
+    ("ins", &["cite", "datetime"]),
+    ("li", &["value"]),
+    ("ol", &["reversed", "start", "type"]),
+    ("q", &["cite"]),
+    ("td", &["align", "colspan", "rowspan"]),
+    ("th", &["align", "colspan", "rowspan"]),
+];
+const DEFAULT_URL_SCHEMES: [&str; 3] = ["http", "https", "mailto"];
+/// Elements whose contents are removed along with them when they aren't allowed.
+const CONTENT_TAGS: [&str; 10] = [
+    "iframe",
+    "noembed",
+    "noframes",
+    "noscript",
+    "plaintext",
+    "script",
+    "style",
+    "template",
+    "textarea",
+    "xmp",
+];
+/// Elements that are never left open for the following fragments of inline HTML, because they
+/// change how the HTML after them is parsed.
+const UNCARRIED_TAGS: [&str; 6] =
+    ["math", "optgroup", "option", "select", "svg", "title"];
+/// Elements that have no contents, and so are never left open.
+const VOID_TAGS: [&str; 13] = [
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta",
+    "source", "track", "wbr",
+];
+/// Elements that inline HTML blocks can leave open to wrap the Markdown after them.
+const CONTAINER_TAGS: [&str; 7] = [
+    "article",
+    "aside",
+    "blockquote",
+    "details",
+    "div",
+    "figure",
+    "section",
+];
+/// Marks the start of the inline HTML that the renderer passes to [`HtmlSanitizer`].
+pub(super) const UNTRUSTED_START: char = '\u{E002}';
+/// Marks the end of the inline HTML that the renderer passes to [`HtmlSanitizer`].
+pub(super) const UNTRUSTED_END: char = '\u{E003}';
+/// Marks the start of the HTML of a list of blocks, such as the contents of a block quote.
+pub(super) const BLOCKS_START: char = '\u{E004}';
+/// Marks the start of the HTML of a list of inlines, such as the contents of a paragraph.
+pub(super) const INLINES_START: char = '\u{E005}';
+/// Marks the end of the HTML started by [`BLOCKS_START`] or [`INLINES_START`].
+pub(super) const SCOPE_END: char = '\u{E006}';
+
+/// Allow-list based sanitizer for the inline HTML of Markdown files, built on [`ammonia`].
+///
+/// Tags, attributes and URL schemes that aren't allowed are removed, as are comments and the
+/// contents of elements such as `<script>` and `<style>`. Relative URLs are always allowed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HtmlSanitizer {
+    /// Tags that are kept.
+    pub tags: BTreeSet<String>,
+    /// Attributes that are kept on every allowed tag.
+    pub generic_attributes: BTreeSet<String>,
+    /// Attributes that are kept on specific tags.
+    pub tag_attributes: BTreeMap<String, BTreeSet<String>>,
+    /// Schemes that URLs in attributes such as `href` and `src` can use.
+    pub url_schemes: BTreeSet<String>,
+}
+
+impl Default for HtmlSanitizer {
+    fn default() -> Self {
+        Self {
+            tags: DEFAULT_TAGS.iter().map(ToString::to_string).collect(),
+            generic_attributes: DEFAULT_GENERIC_ATTRIBUTES
+                .iter()
+                .map(ToString::to_string)
+                .collect(),
+            tag_attributes: DEFAULT_TAG_ATTRIBUTES
+                .iter()
+                .map(|(tag, attributes)| {
+                    (
+                        (*tag).to_string(),
+                        attributes.iter().map(ToString::to_string).collect(),
+                    )
+                })
+                .collect(),
+            url_schemes: DEFAULT_URL_SCHEMES
+                .iter()
+                .map(ToString::to_string)
+                .collect(),
+        }
+    }
+}
+
+impl HtmlSanitizer {
+    /// Allows an attribute, either on every tag or, given as `tag.attribute`, on a single one.
+    pub fn allow_attribute(&mut self, attribute: &str) {
+        let attribute = attribute.to_lowercase();
+        match attribute.split_once('.') {
+            Some((tag, name)) => {
+                self.tag_attributes
+                    .entry(tag.to_string())
+                    .or_default()
+                    .insert(name.to_string());
+            }
+            None => {
+                self.generic_attributes.insert(attribute);
+            }
+        }
+    }
+
+    /// Removes the tags, attributes and URLs that aren't allowed from a fragment of HTML.
+    #[must_use]
+    pub fn sanitize(&self, html: &str) -> String {
+        self.builder().clean(html).to_string()
+    }
+
+    /// Sanitizes the inline HTML of a compiled document, delimited by [`UNTRUSTED_START`] and
+    /// [`UNTRUSTED_END`], leaving the rest of it as is.
+    ///
+    /// Each fragment of inline HTML is sanitized on its own, so none of them can change how the
+    /// rest of the document is parsed. A fragment can leave elements open for the ones after it,
+    /// though, which is how `<kbd>Ctrl</kbd>` or a `<details>` wrapping Markdown work: inline HTML
+    /// within a paragraph can leave any element open, and HTML blocks only the ones in
+    /// [`CONTAINER_TAGS`]. Elements that aren't closed by the end of the blocks or inlines they're
+    /// in, delimited by [`BLOCKS_START`] or [`INLINES_START`] and [`SCOPE_END`], are closed there.
+    pub(super) fn sanitize_untrusted(&self, html: &str) -> String {
+        let mut output = String::with_capacity(html.len());
+        let mut scopes = Vec::new();
+        let mut open = Vec::new();
+        // Element whose contents are being removed, like the parser does within a fragment.
+        let mut removed: Option<OpenElement> = None;
+        let mut rest = html;
+        while let Some(index) = rest.find(is_sanitizer_marker) {
+            if removed.is_none() {
+                output.push_str(&rest[..index]);
+            }
+            let marker = rest[index..].chars().next().unwrap_or(SCOPE_END);
+            rest = &rest[index + marker.len_utf8()..];
+
+            match marker {
+                UNTRUSTED_START => {
+                    let end = rest.find(UNTRUSTED_END).unwrap_or(rest.len());
+                    let mut fragment = &rest[..end];
+                    rest = rest
+                        .get(end + UNTRUSTED_END.len_utf8()..)
+                        .unwrap_or("");
+
+                    let depth = scopes.len();
+                    if let Some(element) = &removed {
+                        match leading_end_tag(fragment) {
+                            Some((_, name, after))
+                                if element.depth == depth
+                                    && element.name == name =>
+                            {
+                                fragment = after;
+                            }
+                            _ => continue,
+                        }
+                    }
+                    let scope = Scope {
+                        depth,
+                        inline: scopes.last() == Some(&INLINES_START),
+                    };
+                    removed = self
+                        .sanitize_fragment(
+                            fragment,
+                            scope,
+                            &mut open,
+                            &mut output,
+                        )
+                        .map(|name| OpenElement { name, depth });
+                }
+                BLOCKS_START | INLINES_START => scopes.push(marker),
+                SCOPE_END => {
+                    if removed
+                        .as_ref()
+                        .is_some_and(|element| element.depth == scopes.len())
+                    {
+                        removed = None;
+                    }
+                    close_elements(&mut open, &mut output, scopes.len());
+                    scopes.pop();
+                }
+                _ => {}
+            }
+        }
+        if removed.is_none() {
+            output.push_str(rest);
+        }
+        close_elements(&mut open, &mut output, 0);
+
+        output
+    }
+
+    /// Sanitizes a `fragment` of inline HTML in `scope`, first closing the elements that earlier
+    /// fragments left `open`, and then leaving open the ones it doesn't close.
+    ///
+    /// Returns the name of the element that the fragment opens within inlines without closing it,
+    /// if its contents are removed along with it, so the inlines up to its end tag are removed too.
+    fn sanitize_fragment(
+        &self,
+        fragment: &str,
+        scope: Scope,
+        open: &mut Vec<OpenElement>,
+        output: &mut String,
+    ) -> Option<String> {
+        let mut fragment = fragment;
+        while let Some((whitespace, name, rest)) = leading_end_tag(fragment)
+            && open.last().is_some_and(|element| {
+                element.depth == scope.depth && element.name == name
+            })
+        {
+            output.push_str(whitespace);
+            push_end_tag(output, &name);
+            open.pop();
+            fragment = rest;
+        }
+
+        let sanitized = self.sanitize(fragment);
+        let removed =
+            self.unclosed_content_tag(fragment).filter(|_| scope.inline);
+        let unclosed = self.unclosed_elements(fragment, scope.inline);
+        // The parser closes the unclosed elements at the end of the fragment, which is where
+        // they're taken out to be left open. If it didn't, the fragment is kept balanced.
+        let mut kept = sanitized.as_str();
+        for name in unclosed.iter().rev() {
+            let Some(rest) = kept.strip_suffix(&format!("</{name}>")) else {
+                output.push_str(&sanitized);
+                return removed;
+            };
+            kept = rest;
+        }
+
+        output.push_str(kept);
+        open.extend(unclosed.into_iter().map(|name| OpenElement {
+            name,
+            depth: scope.depth,
+        }));
+
+        removed
+    }
+
+    /// Returns the last element of `fragment` whose contents are removed along with it, if the
+    /// fragment doesn't close it.
+    fn unclosed_content_tag(&self, fragment: &str) -> Option<String> {
+        let fragment = fragment.to_ascii_lowercase();
+        CONTENT_TAGS
+            .into_iter()
+            .filter(|tag| !self.tags.contains(*tag))
+            .filter_map(|tag| {
+                let open = format!("<{tag}");
+                let start = fragment
+                    .match_indices(&open)
+                    .map(|(index, _)| index)
+                    .filter(|index| {
+                        !fragment[index + open.len()..]
+                            .starts_with(|c: char| c.is_ascii_alphanumeric())
+                    })
+                    .last()?;
+                let closed = fragment[start..].contains(&format!("</{tag}"));
+                (!closed).then_some((start, tag))
+            })
+            .max()
+            .map(|(_, tag)| tag.to_string())
+    }
+
+    /// Returns the elements that `fragment` opens and doesn't close, outermost first, if they can
+    /// be left open: any allowed element `inline`, and only containers otherwise.
+    fn unclosed_elements(&self, fragment: &str, inline: bool) -> Vec<String> {
+        let mut unclosed = Vec::new();
+        let mut rest = fragment;
+        while let Some(index) = rest.find('<') {
+            rest = &rest[index + 1..];
+            // Comments, doctypes and raw text elements aren't worth understanding here, so
+            // fragments with them are always balanced.
+            if rest.starts_with('!') {
+                return Vec::new();
+            }
+            let (closing, tag) = rest
+                .strip_prefix('/')
+                .map_or((false, rest), |tag| (true, tag));
+            let length = tag
+                .find(|c: char| !c.is_ascii_alphanumeric())
+                .unwrap_or(tag.len());
+            if length == 0 {
+                continue;
+            }
+            let name = tag[..length].to_ascii_lowercase();
+            if CONTENT_TAGS.contains(&name.as_str()) {
+                return Vec::new();
+            }
+            let Some(end) = tag_end(&tag[length..]) else {
+                break;
+            };
+            rest = &tag[length + end..];
+
+            if closing {
+                match unclosed.iter().rposition(|open| *open == name) {
+                    Some(index) if index + 1 == unclosed.len() => {
+                        unclosed.pop();
+                    }
+                    Some(_) => return Vec::new(),
+                    None => {}
+                }
+            } else if self.tags.contains(&name)
+                && !VOID_TAGS.contains(&name.as_str())
+                && !UNCARRIED_TAGS.contains(&name.as_str())
+                && (inline || CONTAINER_TAGS.contains(&name.as_str()))
+            {
+                unclosed.push(name);
+            }
+        }
+
+        unclosed
+    }
+
+    fn builder(&self) -> ammonia::Builder<'_> {
+        let mut builder = ammonia::Builder::empty();
+        builder
+            .tags(self.tags.iter().map(String::as_str).collect())
+            .clean_content_tags(
+                CONTENT_TAGS
+                    .into_iter()
+                    .filter(|tag| {
+                        !self.tags.contains(*tag)
+                            && !self.tag_attributes.contains_key(*tag)
+                    })
+                    .collect(),
+            )
+            .generic_attributes(
+                self.generic_attributes.iter().map(String::as_str).collect(),
+            )
+            .tag_attributes(
+                self.tag_attributes
+                    .iter()
+                    .map(|(tag, attributes)| {
+                        (
+                            tag.as_str(),
+                            attributes.iter().map(String::as_str).collect(),
+                        )
+                    })
+                    .collect(),
+            )
+            .url_schemes(self.url_schemes.iter().map(String::as_str).collect())
+            .url_relative(ammonia::UrlRelative::PassThrough)
+            .link_rel(None)
+            .strip_comments(true);
+
+        builder
+    }
+}
+
+/// Returns whether `c` is one of the characters used to delimit inline HTML and its scopes, which
+/// are escaped as character references in the input.
+pub(super) const fn is_sanitizer_marker(c: char) -> bool {
+    matches!(
+        c,
+        UNTRUSTED_START
+            | UNTRUSTED_END
+            | BLOCKS_START
+            | INLINES_START
+            | SCOPE_END
+    )
+}
+
+/// Blocks or inlines that a fragment of inline HTML is in.
+#[derive(Debug, Clone, Copy)]
+struct Scope {
+    depth: usize,
+    inline: bool,
+}
+
+/// Element left open by a fragment of inline HTML, in the scope at `depth`.
+#[derive(Debug)]
+struct OpenElement {
+    name: String,
+    depth: usize,
+}
+
+/// Closes the `open` elements of the scopes at `depth` and deeper.
+fn close_elements(
+    open: &mut Vec<OpenElement>,
+    output: &mut String,
+    depth: usize,
+) {
+    while let Some(element) = open.pop_if(|element| element.depth >= depth) {
+        if CONTAINER_TAGS.contains(&element.name.as_str())
+            && !matches!(output.chars().last(), None | Some('\n' | '\r'))
+        {
+            output.push('\n');
+        }
+        push_end_tag(output, &element.name);
+    }
+}
+
+fn push_end_tag(output: &mut String, name: &str) {
+    output.push_str("</");
+    output.push_str(name);
+    output.push('>');
+}
+
+/// Splits the end tag at the start of `html`, after any whitespace, into the whitespace, the
+/// tag's lowercase name and the rest of `html`.
+fn leading_end_tag(html: &str) -> Option<(&str, String, &str)> {
+    let tag = html.trim_start();
+    let whitespace = &html[..html.len() - tag.len()];
+    let tag = tag.strip_prefix("</")?;
+    let length = tag
+        .find(|c: char| !c.is_ascii_alphanumeric())
+        .unwrap_or(tag.len());
+    if length == 0 {
+        return None;
+    }
+    let end = tag_end(&tag[length..])?;
+
+    Some((
+        whitespace,
+        tag[..length].to_ascii_lowercase(),
+        &tag[length + end..],
+    ))
+}
+
+/// Returns the length of the rest of a tag, after its name, up to and including its `>`.
+fn tag_end(rest: &str) -> Option<usize> {
+    let mut quote = None;
+    for (index, c) in rest.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '>') => return Some(index + 1),
+            (Some(open), _) if open == c => quote = None,
+            _ => {}
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    #[rstest]
+    #[case::allowed_tags(
+        "<details open><summary>More</summary><kbd>Ctrl</kbd></details>",
+        "<details open=\"\"><summary>More</summary><kbd>Ctrl</kbd></details>"
+    )]
+    #[case::script("a<script>alert(1)</script>b", "ab")]
+    #[case::event_handlers(
+        "<img src=\"a.png\" onerror=\"alert(1)\" alt='An \"image\"'/>",
+        "<img src=\"a.png\" alt=\"An &quot;image&quot;\">"
+    )]
+    #[case::unknown_tags("<marquee behavior=alternate>Text</marquee>", "Text")]
+    #[case::javascript_urls(
+        "<a href=\"javascript:alert(1)\" title=x>a</a>",
+        "<a title=\"x\">a</a>"
+    )]
+    #[case::hidden_schemes(
+        "<a href=\"java&#x09;script&colon;alert(1)\">a</a>\
+        <a href=\" JAVASCRIPT&#58;alert(1)\">b</a>",
+        "<a>a</a><a>b</a>"
+    )]
+    #[case::allowed_urls(
+        "<a href=\"https://example.com\">a</a><a href=\"../b.html#c:d\">b</a>",
+        "<a href=\"https://example.com\">a</a><a href=\"../b.html#c:d\">b</a>"
+    )]
+    #[case::comments("a<!-- <script> -->b", "ab")]
+    #[case::stray_brackets("a < b <3 <", "a &lt; b &lt;3 &lt;")]
+    #[case::uppercase("<KBD CLASS=\"x\">a</KBD>", "<kbd class=\"x\">a</kbd>")]
+    fn sanitize_removes_what_is_not_allowed(
+        #[case] input: &str,
+        #[case] expected: &str,
+    ) {
+        assert_eq!(HtmlSanitizer::default().sanitize(input), expected);
+    }
+
+    #[test]
+    fn sanitize_uses_configured_allow_lists() {
+        let mut sanitizer = HtmlSanitizer::default();
+        sanitizer.tags.insert("video".to_string());
+        sanitizer.tags.remove("img");
+        sanitizer.allow_attribute("video.src");
+        sanitizer.allow_attribute("data-id");
+        sanitizer.url_schemes.insert("ftp".to_string());
+
+        assert_eq!(
+            sanitizer.sanitize(
+                "<video src=\"ftp://a/b.mp4\" data-id=\"1\"></video><img src=\"a.png\">"
+            ),
+            "<video src=\"ftp://a/b.mp4\" data-id=\"1\"></video>"
+        );
+    }
+}
@@ -0,0 +1,188 @@
+use super::{
+    MarkdownHeading, MarkdownOptions,
+    rendering::{FootnoteScope, render_blocks},
+};
+use markdown::mdast::Node;
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// A section of a Markdown document, made of everything under one of its headings.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Section {
+    /// Level of the section's heading, from 1 to 6.
+    pub level: u8,
+    /// Text of the section's heading.
+    pub title: String,
+    /// Anchor id of the section's heading.
+    pub id: String,
+    /// Compiled HTML of everything between the heading and the next one of the same or a higher
+    /// level, without the heading itself and without a footnote section.
+    pub content: String,
+    /// Compiled HTML of the section listing the footnotes of `content`, or an empty string if it
+    /// has none.
+    pub footnotes: String,
+}
+
+/// Splits the contents of a Markdown ast into a section for each of its headings, in document
+/// order.
+///
+/// A section includes the sections of the lower level headings under it, so those are compiled
+/// once for each level. Headings inside other blocks, such as blockquotes, don't start sections.
+///
+/// Each section's footnotes are numbered from 1, with ids prefixed by the id of its heading, as in
+/// `user-content-fn-pricing-1`, so several sections can be placed on the same page.
+///
+/// Headings get the ids of the matching entries in `headings`, which should come from
+/// [`collect_md_headings`](super::collect_md_headings) on the same ast.
+#[must_use]
+pub fn split_md_sections(
+    root: &Node,
+    headings: &[MarkdownHeading],
+    options: &MarkdownOptions,
+) -> Vec<Section> {
+    let ids: HashMap<_, _> = headings
+        .iter()
+        .filter_map(|heading| Some((heading.offset?, heading)))
+        .collect();
+
+    let mut definitions = Vec::new();
+    let mut blocks = Vec::new();
+    for child in root.children().into_iter().flatten() {
+        match child {
+            Node::Toml(_) | Node::Yaml(_) => {}
+            Node::Definition(_) | Node::FootnoteDefinition(_) => {
+                definitions.push(child.clone());
+            }
+            _ => blocks.push(child),
+        }
+    }
+
+    let mut sections = Vec::new();
+    for (index, block) in blocks.iter().enumerate() {
+        let Node::Heading(heading) = block else {
+            continue;
+        };
+        let Some(found) = heading
+            .position
+            .as_ref()
+            .and_then(|position| ids.get(&position.start.offset))
+        else {
+            continue;
+        };
+
+        let end = blocks[index + 1..]
+            .iter()
+            .position(|block| {
+                matches!(block, Node::Heading(next) if next.depth <= heading.depth)
+            })
+            .map_or(blocks.len(), |end| index + 1 + end);
+        let content = blocks[index + 1..end].iter().copied().cloned().collect();
+
+        let scope = FootnoteScope {
+            prefix: format!("{}-", found.id),
+            offset: 0,
+        };
+        let (content, footnotes) =
+            render_blocks(content, &definitions, headings, options, &scope);
+        sections.push(Section {
+            level: found.level,
+            title: found.text.clone(),
+            id: found.id.clone(),
+            content,
+            footnotes: footnotes.section,
+        });
+    }
+
+    sections
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::templates::{HtmlSanitizer, collect_md_headings, parse_md};
+
+    fn split(input: &str) -> Vec<Section> {
+        let options = MarkdownOptions::default();
+        let mut ast =
+            parse_md(input, &options).expect("Failed to parse markdown input");
+        let headings = collect_md_headings(&mut ast);
+
+        split_md_sections(&ast, &headings, &options)
+    }
+
+    #[test]
+    fn sections_end_at_the_next_heading_of_their_level() {
+        let sections = split(
+            "Intro\n\n## Features {#features}\n\nFast[^1].\n\n### Details\n\n\
+            Very *fast*.\n\n## Pricing\n\nFree[^2].\n\n# FAQ\n\n[^1]: Really.\n\n\
+            [^2]: Mostly.",
+        );
+
+        let contents: Vec<_> = sections
+            .iter()
+            .map(|section| {
+                (section.level, section.id.as_str(), section.title.as_str())
+            })
+            .collect();
+        assert_eq!(
+            contents,
+            vec![
+                (2, "features", "Features"),
+                (3, "details", "Details"),
+                (2, "pricing", "Pricing"),
+                (1, "faq", "FAQ"),
+            ]
+        );
+        assert_eq!(
+            sections[0].content,
+            "<p>Fast<sup><a href=\"#user-content-fn-features-1\" \
+            id=\"user-content-fnref-features-1\" data-footnote-ref=\"\" \
+            aria-describedby=\"features-footnote-label\">1</a></sup>.</p>\n\
+            <h3 id=\"details\">Details</h3>\n<p>Very <em>fast</em>.</p>"
+        );
+        assert!(sections[0].footnotes.contains(
+            "<li id=\"user-content-fn-features-1\">\n<p>Really. \
+            <a href=\"#user-content-fnref-features-1\""
+        ));
+        assert_eq!(sections[1].content, "<p>Very <em>fast</em>.</p>");
+        assert_eq!(sections[1].footnotes, "");
+        assert_eq!(
+            sections[2].content,
+            "<p>Free<sup><a href=\"#user-content-fn-pricing-2\" \
+            id=\"user-content-fnref-pricing-2\" data-footnote-ref=\"\" \
+            aria-describedby=\"pricing-footnote-label\">1</a></sup>.</p>"
+        );
+        assert!(sections[2].footnotes.contains(
+            "<li id=\"user-content-fn-pricing-2\">\n<p>Mostly. \
+            <a href=\"#user-content-fnref-pricing-2\""
+        ));
+        assert_eq!(sections[3].content, "");
+    }
+
+    #[test]
+    fn section_footnote_ids_are_encoded() {
+        let options = MarkdownOptions {
+            sanitizer: Some(HtmlSanitizer::default()),
+            ..MarkdownOptions::default()
+        };
+        let mut ast = parse_md(
+            "## Hi {#x\"onmouseover=\"alert(1)}\n\nText[^1].\n\n[^1]: Note.",
+            &options,
+        )
+        .expect("Failed to parse markdown input");
+        let headings = collect_md_headings(&mut ast);
+        let mut headings_with_quotes = headings.clone();
+        headings_with_quotes[0].id = "x\"onmouseover=\"alert(1)".to_string();
+
+        for headings in [headings, headings_with_quotes] {
+            let sections = split_md_sections(&ast, &headings, &options);
+            assert!(!sections[0].content.contains("\"onmouseover"));
+            assert!(!sections[0].footnotes.contains("\"onmouseover"));
+        }
+    }
+
+    #[test]
+    fn nested_headings_are_not_sections() {
+        assert!(split("> # Quoted\n\nText").is_empty());
+    }
+}
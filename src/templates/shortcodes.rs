@@ -0,0 +1,749 @@
+use super::{extraction::frontmatter_length, trusted_html};
+use camino::Utf8Path;
+use color_eyre::eyre::{Context, eyre};
+use markdown::mdast::{Node, Text};
+use std::collections::BTreeMap;
+use tera::Tera;
+
+const PLACEHOLDER_START: char = '\u{E000}';
+const PLACEHOLDER_END: char = '\u{E001}';
+/// How the placeholder characters written in the input are kept apart from placeholders.
+const ESCAPED_PLACEHOLDER_START: &str = "\u{E000}\u{E001}";
+
+/// Tera templates that Markdown files can call as `{{< name key="value" >}}` or
+/// `{% name(key="value") %}` shortcodes, keyed by name.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Shortcodes {
+    pub templates: BTreeMap<String, String>,
+}
+
+impl Shortcodes {
+    /// Loads the templates inside `directory`, naming each shortcode after its file's stem.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the directory or any of its files can't be read.
+    pub fn load(directory: &Utf8Path) -> color_eyre::Result<Self> {
+        let mut templates = BTreeMap::new();
+        for entry in directory.read_dir_utf8().wrap_err_with(|| {
+            format!("Failed to open shortcodes directory `{directory}`")
+        })? {
+            let entry = entry?;
+            if !entry.file_type()?.is_file() {
+                continue;
+            }
+            if let Some(name) = entry.path().file_stem() {
+                templates.insert(
+                    name.to_string(),
+                    std::fs::read_to_string(entry.path())?,
+                );
+            }
+        }
+
+        Ok(Self { templates })
+    }
+
+    fn render(&self, call: &ShortcodeCall) -> color_eyre::Result<String> {
+        let template = self
+            .templates
+            .get(&call.name)
+            .ok_or_else(|| eyre!("Unknown shortcode `{}`", call.name))?;
+
+        let mut ctx = tera::Context::new();
+        for (key, value) in &call.arguments {
+            ctx.insert(key.clone(), value);
+        }
+        if let Some(body) = &call.body {
+            let body = replace_placeholders(body, &call.nested, |nested| {
+                self.render(nested)
+            })?;
+            ctx.insert("body", &body);
+        }
+
+        Tera::one_off(template, &ctx, true).wrap_err_with(|| {
+            format!("Failed to render shortcode `{}`", call.name)
+        })
+    }
+}
+
+type Arguments = Vec<(String, toml::Value)>;
+
+/// How a shortcode is written in a Markdown file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Syntax {
+    /// Hugo's `{{< name key="value" >}}`, closed by `{{< /name >}}` when it has a body.
+    Hugo,
+    /// Zola's `{% name(key="value") %}`, closed by `{% end %}` when it has a body.
+    Zola,
+}
+
+impl Syntax {
+    const fn opening(self) -> &'static str {
+        match self {
+            Self::Hugo => "{{<",
+            Self::Zola => "{%",
+        }
+    }
+
+    const fn closing(self) -> &'static str {
+        match self {
+            Self::Hugo => ">}}",
+            Self::Zola => "%}",
+        }
+    }
+
+    /// Characters that can quote a string argument.
+    const fn quotes(self) -> &'static [char] {
+        match self {
+            Self::Hugo => &['"'],
+            Self::Zola => &['"', '\'', '`'],
+        }
+    }
+}
+
+/// A shortcode found in a Markdown `&str`.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct ShortcodeCall {
+    name: String,
+    arguments: Arguments,
+    /// Content between the opening and closing tags of a paired shortcode, with placeholders in
+    /// place of its own shortcodes.
+    body: Option<String>,
+    /// Shortcodes called in the body, in the order of their placeholders.
+    nested: Vec<Self>,
+    /// Markdown source of the whole call.
+    source: String,
+}
+
+impl ShortcodeCall {
+    /// Renders the string arguments and the body of the call as Tera templates with `ctx`, the
+    /// way the rest of the Markdown body is rendered with `--render-body`.
+    pub(super) fn render_templates(
+        &mut self,
+        ctx: &tera::Context,
+    ) -> color_eyre::Result<()> {
+        let name = &self.name;
+        let render = |value: &str| {
+            Tera::one_off(value, ctx, false).wrap_err_with(|| {
+                format!("Failed to render the arguments of shortcode `{name}`")
+            })
+        };
+
+        for (_, value) in &mut self.arguments {
+            if let toml::Value::String(value) = value {
+                *value = render(value)?;
+            }
+        }
+        if let Some(body) = &mut self.body {
+            *body = render(body)?;
+        }
+        for nested in &mut self.nested {
+            nested.render_templates(ctx)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Replaces the shortcodes of a Markdown `&str` with placeholders, so they go through parsing
+/// untouched, returning the new input and the calls in the order of their placeholders.
+///
+/// Shortcodes in the frontmatter are left as they are.
+pub(super) fn extract_md_shortcodes(
+    input: &str,
+) -> (String, Vec<ShortcodeCall>) {
+    let (frontmatter, rest) = input.split_at(frontmatter_length(input));
+    let (rest, calls) = extract_shortcodes(rest);
+
+    (format!("{frontmatter}{rest}"), calls)
+}
+
+fn extract_shortcodes(input: &str) -> (String, Vec<ShortcodeCall>) {
+    let mut output = String::with_capacity(input.len());
+    let mut calls = Vec::new();
+    let mut rest = input;
+
+    while let Some((start, syntax)) = next_tag(rest) {
+        let (before, tag) = rest.split_at(start);
+        push_escaped(&mut output, before);
+
+        let Some((name, arguments, length)) = parse_opening_tag(tag, syntax)
+        else {
+            let opening = syntax.opening();
+            output.push_str(opening);
+            rest = &tag[opening.len()..];
+            continue;
+        };
+
+        let after = &tag[length..];
+        let closing = closing_tag(after, &name, syntax);
+        let (body, nested) =
+            closing.map_or((None, Vec::new()), |(start, _)| {
+                let (body, nested) = extract_shortcodes(&after[..start]);
+                (Some(body), nested)
+            });
+        let length = closing.map_or(length, |(_, end)| length + end);
+
+        output.push(PLACEHOLDER_START);
+        output.push_str(&calls.len().to_string());
+        output.push(PLACEHOLDER_END);
+        calls.push(ShortcodeCall {
+            name,
+            arguments,
+            body,
+            nested,
+            source: tag[..length].to_string(),
+        });
+        rest = &tag[length..];
+    }
+    push_escaped(&mut output, rest);
+
+    (output, calls)
+}
+
+/// Pushes text from the input, escaping the placeholder characters it may contain.
+fn push_escaped(output: &mut String, text: &str) {
+    for (index, part) in text.split(PLACEHOLDER_START).enumerate() {
+        if index > 0 {
+            output.push_str(ESCAPED_PLACEHOLDER_START);
+        }
+        output.push_str(part);
+    }
+}
+
+/// Finds the start of the next tag that could be a shortcode, in either syntax.
+fn next_tag(input: &str) -> Option<(usize, Syntax)> {
+    [Syntax::Hugo, Syntax::Zola]
+        .into_iter()
+        .filter_map(|syntax| Some((input.find(syntax.opening())?, syntax)))
+        .min_by_key(|(start, _)| *start)
+}
+
+/// Parses a `{{< name key=value >}}` or `{% name(key=value) %}` tag, returning its name, its
+/// arguments and its length.
+fn parse_opening_tag(
+    tag: &str,
+    syntax: Syntax,
+) -> Option<(String, Arguments, usize)> {
+    let start = syntax.opening().len();
+    let end = tag.find(syntax.closing())?;
+    let rest = tag[start..end].trim();
+
+    let name_length = rest
+        .find(|c: char| !(c.is_alphanumeric() || c == '-' || c == '_'))
+        .unwrap_or(rest.len());
+    if name_length == 0 {
+        return None;
+    }
+    let name = rest[..name_length].to_string();
+    let rest = &rest[name_length..];
+
+    let arguments = match syntax {
+        Syntax::Hugo => parse_arguments(rest, syntax)?,
+        // Anything else, such as `{% if x %}`, is left for Tera.
+        Syntax::Zola => {
+            parse_arguments(rest.strip_prefix('(')?.strip_suffix(')')?, syntax)?
+        }
+    };
+
+    Some((name, arguments, end + syntax.closing().len()))
+}
+
+/// Parses the `key=value` arguments of a tag, separated by whitespace, or by commas in Zola's
+/// syntax.
+fn parse_arguments(mut rest: &str, syntax: Syntax) -> Option<Arguments> {
+    let mut arguments = Vec::new();
+    loop {
+        rest = rest.trim_start();
+        if rest.is_empty() {
+            break;
+        }
+
+        let (key, after) = rest.split_once('=')?;
+        let key = key.trim();
+        if key.is_empty() || key.contains(char::is_whitespace) {
+            return None;
+        }
+
+        let after = after.trim_start();
+        let (value, after) = match after.chars().next() {
+            Some(quote) if syntax.quotes().contains(&quote) => {
+                let (value, after) = quoted_value(&after[1..], quote)?;
+                (toml::Value::String(value), after)
+            }
+            _ => {
+                let end = after
+                    .find(|c: char| {
+                        c.is_whitespace()
+                            || (syntax == Syntax::Zola && c == ',')
+                    })
+                    .unwrap_or(after.len());
+                (argument_value(&after[..end]), &after[end..])
+            }
+        };
+
+        arguments.push((key.to_string(), value));
+        rest = after;
+        if syntax == Syntax::Zola {
+            rest = rest.trim_start();
+            if let Some(after) = rest.strip_prefix(',') {
+                rest = after;
+            } else if !rest.is_empty() {
+                return None;
+            }
+        }
+    }
+
+    Some(arguments)
+}
+
+/// Reads a quoted string up to its closing `quote`, returning it and the rest of the input.
+///
+/// A `\` escapes the quote or another `\`, and is kept before any other character.
+fn quoted_value(input: &str, quote: char) -> Option<(String, &str)> {
+    let mut value = String::new();
+    let mut chars = input.char_indices();
+    while let Some((index, c)) = chars.next() {
+        if c == '\\' {
+            let (_, escaped) = chars.next()?;
+            if escaped != quote && escaped != '\\' {
+                value.push(c);
+            }
+            value.push(escaped);
+        } else if c == quote {
+            return Some((value, &input[index + c.len_utf8()..]));
+        } else {
+            value.push(c);
+        }
+    }
+
+    None
+}
+
+fn argument_value(value: &str) -> toml::Value {
+    value
+        .parse()
+        .map(toml::Value::Boolean)
+        .or_else(|_| value.parse().map(toml::Value::Integer))
+        .or_else(|_| value.parse().map(toml::Value::Float))
+        .unwrap_or_else(|_| toml::Value::String(value.to_string()))
+}
+
+/// Finds the `{{< /name >}}` or `{% end %}` tag closing a paired shortcode, returning its start
+/// and end.
+///
+/// Shortcodes can be nested, so each one inside the body that could take the same closing tag
+/// needs its own first. Hugo's closing tags name their shortcode, while Zola's `{% end %}` closes
+/// the closest shortcode before it that isn't closed yet, so `{% name() %}` without one after it
+/// has no body.
+fn closing_tag(
+    input: &str,
+    name: &str,
+    syntax: Syntax,
+) -> Option<(usize, usize)> {
+    let (opening, closing) = (syntax.opening(), syntax.closing());
+    let mut depth = 0;
+    let mut offset = 0;
+    while let Some(start) = input[offset..].find(opening) {
+        let start = offset + start;
+        let end = input[start..].find(closing)? + start + closing.len();
+        let tag = input[start + opening.len()..end - closing.len()].trim();
+        let closes = match syntax {
+            Syntax::Hugo => tag
+                .strip_prefix('/')
+                .is_some_and(|closing| closing.trim() == name),
+            Syntax::Zola => tag == "end",
+        };
+        if closes {
+            if depth == 0 {
+                return Some((start, end));
+            }
+            depth -= 1;
+        } else if parse_opening_tag(&input[start..], syntax).is_some_and(
+            |(opening, ..)| syntax == Syntax::Zola || opening == name,
+        ) {
+            depth += 1;
+        }
+        offset = end;
+    }
+
+    None
+}
+
+/// Replaces the placeholders left by `extract_md_shortcodes` with the rendered shortcodes.
+///
+/// A shortcode alone in its paragraph replaces the whole paragraph. Shortcodes inside code are
+/// put back as they were written.
+pub(super) fn expand_md_shortcodes(
+    root: &mut Node,
+    calls: &[ShortcodeCall],
+    shortcodes: &Shortcodes,
+) -> color_eyre::Result<()> {
+    match root {
+        Node::Code(code) => code.value = restore_sources(&code.value, calls),
+        Node::InlineCode(code) => {
+            code.value = restore_sources(&code.value, calls);
+        }
+        Node::Math(math) => math.value = restore_sources(&math.value, calls),
+        Node::InlineMath(math) => {
+            math.value = restore_sources(&math.value, calls);
+        }
+        Node::Image(image) => {
+            image.alt = restore_sources(&image.alt, calls);
+            image.url = restore_sources(&image.url, calls);
+        }
+        Node::Link(link) => link.url = restore_sources(&link.url, calls),
+        Node::Definition(definition) => {
+            definition.url = restore_sources(&definition.url, calls);
+        }
+        Node::Html(html) => {
+            html.value = render_placeholders(&html.value, calls, shortcodes)?;
+        }
+        _ => {}
+    }
+
+    let Some(children) = root.children_mut() else {
+        return Ok(());
+    };
+
+    let mut expanded = Vec::with_capacity(children.len());
+    for mut child in std::mem::take(children) {
+        match &child {
+            Node::Paragraph(paragraph) => {
+                if let [Node::Text(text)] = paragraph.children.as_slice()
+                    && let [Piece::Call(call)] =
+                        split_pieces(text.value.trim(), calls).as_slice()
+                {
+                    let html = shortcodes.render(call)?;
+                    expanded.push(trusted_html(html.trim().to_string()));
+                    continue;
+                }
+            }
+            Node::Text(text) if text.value.contains(PLACEHOLDER_START) => {
+                expanded.extend(split_placeholders(
+                    &text.value,
+                    calls,
+                    shortcodes,
+                )?);
+                continue;
+            }
+            _ => {}
+        }
+
+        expand_md_shortcodes(&mut child, calls, shortcodes)?;
+        expanded.push(child);
+    }
+    *children = expanded;
+
+    Ok(())
+}
+
+enum Piece<'a> {
+    Text(&'a str),
+    Call(&'a ShortcodeCall),
+}
+
+/// Splits a value at its placeholders, turning the escaped placeholder characters of the input
+/// back into text.
+fn split_pieces<'a>(
+    value: &'a str,
+    calls: &'a [ShortcodeCall],
+) -> Vec<Piece<'a>> {
+    let mut pieces = Vec::new();
+    let mut rest = value;
+    while let Some(start) = rest.find(PLACEHOLDER_START) {
+        let after = &rest[start + PLACEHOLDER_START.len_utf8()..];
+        let end = after.find(PLACEHOLDER_END).unwrap_or(after.len());
+        if start > 0 {
+            pieces.push(Piece::Text(&rest[..start]));
+        }
+        // Anything but a call is an escaped placeholder character.
+        let call = after[..end]
+            .parse()
+            .ok()
+            .and_then(|index: usize| calls.get(index));
+        pieces.push(call.map_or_else(
+            || Piece::Text(&rest[start..start + PLACEHOLDER_START.len_utf8()]),
+            Piece::Call,
+        ));
+        rest = after.get(end + PLACEHOLDER_END.len_utf8()..).unwrap_or("");
+    }
+    if !rest.is_empty() {
+        pieces.push(Piece::Text(rest));
+    }
+
+    pieces
+}
+
+/// Splits a text at its placeholders, turning them into HTML nodes.
+fn split_placeholders(
+    value: &str,
+    calls: &[ShortcodeCall],
+    shortcodes: &Shortcodes,
+) -> color_eyre::Result<Vec<Node>> {
+    let mut nodes = Vec::new();
+    let mut text = String::new();
+    for piece in split_pieces(value, calls) {
+        match piece {
+            Piece::Text(value) => text.push_str(value),
+            Piece::Call(call) => {
+                if !text.is_empty() {
+                    nodes.push(Node::Text(Text {
+                        value: std::mem::take(&mut text),
+                        position: None,
+                    }));
+                }
+                nodes.push(trusted_html(shortcodes.render(call)?));
+            }
+        }
+    }
+    if !text.is_empty() {
+        nodes.push(Node::Text(Text {
+            value: text,
+            position: None,
+        }));
+    }
+
+    Ok(nodes)
+}
+
+fn render_placeholders(
+    value: &str,
+    calls: &[ShortcodeCall],
+    shortcodes: &Shortcodes,
+) -> color_eyre::Result<String> {
+    replace_placeholders(value, calls, |call| shortcodes.render(call))
+}
+
+fn restore_sources(value: &str, calls: &[ShortcodeCall]) -> String {
+    replace_placeholders(value, calls, |call| {
+        Ok::<_, color_eyre::Report>(call.source.clone())
+    })
+    .unwrap_or_else(|_| value.to_string())
+}
+
+fn replace_placeholders(
+    value: &str,
+    calls: &[ShortcodeCall],
+    mut replacement: impl FnMut(&ShortcodeCall) -> color_eyre::Result<String>,
+) -> color_eyre::Result<String> {
+    if !value.contains(PLACEHOLDER_START) {
+        return Ok(value.to_string());
+    }
+
+    let mut output = String::with_capacity(value.len());
+    for piece in split_pieces(value, calls) {
+        match piece {
+            Piece::Text(text) => output.push_str(text),
+            Piece::Call(call) => output.push_str(&replacement(call)?),
+        }
+    }
+
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::templates::{MarkdownOptions, parse_md_content};
+    use rstest::*;
+
+    #[fixture]
+    fn options() -> MarkdownOptions {
+        MarkdownOptions {
+            shortcodes: Some(Shortcodes {
+                templates: BTreeMap::from([
+                    (
+                        "youtube".to_string(),
+                        "<iframe src=\"https://www.youtube.com/embed/{{ id }}\"></iframe>"
+                            .to_string(),
+                    ),
+                    (
+                        "kbd".to_string(),
+                        "<kbd>{{ key }}</kbd>".to_string(),
+                    ),
+                    (
+                        "note".to_string(),
+                        "<aside data-level=\"{{ level }}\">{{ body | trim }}</aside>"
+                            .to_string(),
+                    ),
+                ]),
+            }),
+            ..MarkdownOptions::default()
+        }
+    }
+
+    fn render(input: &str, options: &MarkdownOptions) -> String {
+        parse_md_content(input, options)
+            .expect("Failed to parse markdown input")
+    }
+
+    #[rstest]
+    fn shortcodes_replace_their_paragraph(options: MarkdownOptions) {
+        assert_eq!(
+            render("Intro\n\n{{< youtube id=\"abc\" >}}\n\nOutro", &options),
+            "<p>Intro</p>\n\
+            <iframe src=\"https://www.youtube.com/embed/abc\"></iframe>\n\
+            <p>Outro</p>"
+        );
+    }
+
+    #[rstest]
+    fn shortcodes_render_inline(options: MarkdownOptions) {
+        assert_eq!(
+            render("Press {{< kbd key=\"<Ctrl>\" >}} and *go*.", &options),
+            "<p>Press <kbd>&lt;Ctrl&gt;</kbd> and <em>go</em>.</p>"
+        );
+    }
+
+    #[rstest]
+    fn paired_shortcodes_get_their_body(options: MarkdownOptions) {
+        assert_eq!(
+            render("{{< note level=2 >}}\nSome text\n{{< /note >}}", &options),
+            "<aside data-level=\"2\">Some text</aside>"
+        );
+    }
+
+    #[rstest]
+    fn zola_shortcodes_are_rendered(options: MarkdownOptions) {
+        assert_eq!(
+            render(
+                "{% youtube(id=\"abc\") %}\n\n\
+                Press {% kbd(key='Ctrl', ) %}.\n\n\
+                {% note(level=2) %}\nSome `text`\n{% end %}",
+                &options
+            ),
+            "<iframe src=\"https://www.youtube.com/embed/abc\"></iframe>\n\
+            <p>Press <kbd>Ctrl</kbd>.</p>\n\
+            <aside data-level=\"2\">Some `text`</aside>"
+        );
+    }
+
+    #[rstest]
+    fn zola_shortcodes_work_with_templated_bodies(
+        mut options: MarkdownOptions,
+    ) {
+        options.render_body = true;
+
+        assert_eq!(
+            render(
+                "{% if true %}Yes{% endif %}: {% kbd(key=\"a\") %}",
+                &options
+            ),
+            "<p>Yes: <kbd>a</kbd></p>"
+        );
+    }
+
+    #[rstest]
+    #[case::hugo("{{< kbd key=\"say \\\"hi\\\" \\\\ \\n\" >}}")]
+    #[case::zola("{% kbd(key='say \"hi\" \\\\ \\n') %}")]
+    fn quoted_arguments_can_escape_quotes(
+        options: MarkdownOptions,
+        #[case] input: &str,
+    ) {
+        assert_eq!(
+            render(input, &options),
+            "<kbd>say &quot;hi&quot; \\ \\n</kbd>"
+        );
+    }
+
+    #[rstest]
+    fn shortcodes_in_code_are_kept(options: MarkdownOptions) {
+        assert_eq!(
+            render(
+                "`{{< kbd key=\"a\" >}}`\n\n```\n{{< youtube id=\"b\" >}}\n```",
+                &options
+            ),
+            "<p><code>{{&lt; kbd key=&quot;a&quot; &gt;}}</code></p>\n\
+            <pre><code>{{&lt; youtube id=&quot;b&quot; &gt;}}\n</code></pre>"
+        );
+    }
+
+    #[rstest]
+    fn unknown_shortcodes_are_errors(options: MarkdownOptions) {
+        assert!(parse_md_content("{{< missing >}}", &options).is_err());
+    }
+
+    #[rstest]
+    #[case::unterminated("{{< kbd key=\"a\"")]
+    #[case::no_name("{{< >}}")]
+    #[case::no_parentheses("{% kbd key=\"a\" %}")]
+    #[case::unterminated_string("{% kbd(key=\"a) %}")]
+    fn invalid_shortcodes_are_left_as_text(
+        options: MarkdownOptions,
+        #[case] input: &str,
+    ) {
+        assert_eq!(
+            render(input, &options),
+            format!(
+                "<p>{}</p>",
+                input
+                    .replace('"', "&quot;")
+                    .replace('<', "&lt;")
+                    .replace('>', "&gt;")
+            )
+        );
+    }
+
+    #[rstest]
+    fn shortcodes_work_with_templated_bodies(mut options: MarkdownOptions) {
+        options.render_body = true;
+        options.variables =
+            BTreeMap::from([("key".to_string(), "Enter".to_string())]);
+
+        assert_eq!(
+            render("{{ key }}: {{< kbd key=\"Esc\" >}}", &options),
+            "<p>Enter: <kbd>Esc</kbd></p>"
+        );
+    }
+
+    #[rstest]
+    fn shortcode_arguments_and_bodies_are_templated(
+        mut options: MarkdownOptions,
+    ) {
+        options.render_body = true;
+        options.variables = BTreeMap::from([
+            ("video_id".to_string(), "abc".to_string()),
+            ("key".to_string(), "Enter".to_string()),
+        ]);
+
+        assert_eq!(
+            render(
+                "{{< youtube id=\"{{ video_id }}\" >}}\n\n\
+                {{< note level=1 >}}Press {{ key }}{{< /note >}}",
+                &options
+            ),
+            "<iframe src=\"https://www.youtube.com/embed/abc\"></iframe>\n\
+            <aside data-level=\"1\">Press Enter</aside>"
+        );
+    }
+
+    #[rstest]
+    fn paired_shortcodes_can_be_nested(mut options: MarkdownOptions) {
+        if let Some(shortcodes) = &mut options.shortcodes {
+            shortcodes.templates.insert(
+                "box".to_string(),
+                "<div>{{ body | trim | safe }}</div>".to_string(),
+            );
+        }
+
+        assert_eq!(
+            render(
+                "{{< box >}}\na {{< box >}}b {{< kbd key=\"c\" >}}{{< /box >}} d\n{{< /box >}}",
+                &options
+            ),
+            "<div>a <div>b <kbd>c</kbd></div> d</div>"
+        );
+    }
+
+    #[rstest]
+    fn placeholder_characters_in_the_input_are_kept(options: MarkdownOptions) {
+        assert_eq!(
+            render(
+                "\u{E000}0\u{E001} {{< kbd key=\"a\" >}} `\u{E000}`",
+                &options
+            ),
+            "<p>\u{E000}0\u{E001} <kbd>a</kbd> <code>\u{E000}</code></p>"
+        );
+    }
+}
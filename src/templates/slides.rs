@@ -0,0 +1,300 @@
+use super::{
+    MarkdownHeading, MarkdownOptions,
+    extraction::inline_text,
+    rendering::{FootnoteScope, render_blocks},
+    summary::is_summary_marker,
+};
+use color_eyre::eyre::Context;
+use markdown::mdast::Node;
+use serde::Serialize;
+
+/// A slide of a Markdown document split into a deck.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Slide {
+    /// Position of the slide in the deck, starting at 1.
+    pub number: usize,
+    /// Text of the slide's first heading, or an empty string if it has none.
+    pub title: String,
+    /// Table parsed from the slide's `<!-- slide: ... -->` comment.
+    pub attributes: toml::Table,
+    /// Compiled HTML of the slide's contents.
+    pub content: String,
+    /// Compiled HTML of the slide's speaker notes.
+    pub notes: String,
+}
+
+/// Splits the contents of a Markdown ast into slides, starting a new one at every thematic break
+/// (`---`) and level 2 heading, and compiles each of them into HTML.
+///
+/// A `<!-- slide: key = "value", other = 1 -->` comment sets the attributes of the slide it's in,
+/// or of the next one when it's right before a level 2 heading, as TOML. It can span several
+/// lines, with one `key = value` pair on each. These comments are removed from the ast, and the
+/// `<!-- more -->` marker is left out of the slides. Everything from a paragraph starting with
+/// `Note:` or `Notes:` to the end of a slide is its speaker notes. Empty slides are left out.
+///
+/// Footnotes are listed at the end of the slide or of the speaker notes referencing them, and
+/// numbered across the deck. Their ids are prefixed with `slide-1-`, `slide-1-notes-`, etc., so
+/// the slides can be placed on the same page.
+///
+/// # Errors
+///
+/// Returns an error if the attributes of a slide are not valid TOML.
+pub fn split_md_slides(
+    root: &mut Node,
+    headings: &[MarkdownHeading],
+    options: &MarkdownOptions,
+) -> color_eyre::Result<Vec<Slide>> {
+    let slides = split(root, headings, options)?;
+    if let Some(children) = root.children_mut() {
+        children.retain(|node| slide_attributes(node).is_none());
+    }
+
+    Ok(slides)
+}
+
+fn split(
+    root: &Node,
+    headings: &[MarkdownHeading],
+    options: &MarkdownOptions,
+) -> color_eyre::Result<Vec<Slide>> {
+    let mut definitions = Vec::new();
+    let mut groups = Vec::new();
+    let mut group: Vec<&Node> = Vec::new();
+    for child in root.children().into_iter().flatten() {
+        match child {
+            Node::Toml(_) | Node::Yaml(_) => {}
+            _ if is_summary_marker(child) => {}
+            Node::Definition(_) | Node::FootnoteDefinition(_) => {
+                definitions.push(child.clone());
+            }
+            Node::ThematicBreak(_) => groups.push(std::mem::take(&mut group)),
+            Node::Heading(heading) if heading.depth == 2 => {
+                // Attributes right before the heading belong to the slide it starts.
+                let start = group
+                    .iter()
+                    .rposition(|node| slide_attributes(node).is_none())
+                    .map_or(0, |index| index + 1);
+                if start > 0 {
+                    let next = group.split_off(start);
+                    groups.push(std::mem::replace(&mut group, next));
+                }
+                group.push(child);
+            }
+            _ => group.push(child),
+        }
+    }
+    groups.push(group);
+
+    let mut footnote_count = 0;
+    let mut render = |blocks, prefix| {
+        let scope = FootnoteScope {
+            prefix,
+            offset: footnote_count,
+        };
+        let (mut html, footnotes) =
+            render_blocks(blocks, &definitions, headings, options, &scope);
+        footnote_count += footnotes.notes.len();
+        footnotes.append_to(&mut html);
+        html
+    };
+    let mut slides = Vec::new();
+    for group in groups {
+        let mut attributes = toml::Table::new();
+        let mut blocks = Vec::new();
+        for node in group {
+            match slide_attributes(node) {
+                Some(source) => attributes.extend(parse_attributes(&source)?),
+                None => blocks.push(node.clone()),
+            }
+        }
+        if blocks.is_empty() {
+            continue;
+        }
+
+        let notes = blocks
+            .iter_mut()
+            .position(take_notes_marker)
+            .map(|index| blocks.split_off(index))
+            .unwrap_or_default();
+        let title = blocks
+            .iter()
+            .find(|node| matches!(node, Node::Heading(_)))
+            .map(inline_text)
+            .unwrap_or_default();
+
+        let number = slides.len() + 1;
+        slides.push(Slide {
+            number,
+            title,
+            attributes,
+            content: render(blocks, format!("slide-{number}-")),
+            notes: render(notes, format!("slide-{number}-notes-")),
+        });
+    }
+
+    Ok(slides)
+}
+
+/// Returns the source of the attributes of a `<!-- slide: ... -->` comment, if `node` is one.
+fn slide_attributes(node: &Node) -> Option<String> {
+    let comment = match node {
+        Node::Html(html) => html.value.clone(),
+        Node::Paragraph(_) => inline_text(node),
+        _ => return None,
+    };
+
+    comment
+        .trim()
+        .strip_prefix("<!--")
+        .and_then(|comment| comment.strip_suffix("-->"))
+        .and_then(|comment| comment.trim_start().strip_prefix("slide"))
+        .map(|comment| comment.strip_prefix(':').unwrap_or(comment).trim())
+        .map(ToString::to_string)
+}
+
+fn parse_attributes(source: &str) -> color_eyre::Result<toml::Table> {
+    if source.contains('\n') {
+        toml::from_str(source)
+    } else {
+        // A single line is parsed as the contents of an inline table, to allow several pairs.
+        toml::from_str::<toml::Table>(&format!("attributes = {{ {source} }}"))
+            .map(|mut table| match table.remove("attributes") {
+                Some(toml::Value::Table(attributes)) => attributes,
+                _ => toml::Table::new(),
+            })
+    }
+    .wrap_err_with(|| format!("Invalid slide attributes `{source}`"))
+}
+
+/// Removes the `Note:` or `Notes:` prefix of a paragraph, returning whether it had one.
+fn take_notes_marker(node: &mut Node) -> bool {
+    let Node::Paragraph(paragraph) = node else {
+        return false;
+    };
+    let Some(Node::Text(text)) = paragraph.children.first_mut() else {
+        return false;
+    };
+    let Some(rest) = text
+        .value
+        .strip_prefix("Notes:")
+        .or_else(|| text.value.strip_prefix("Note:"))
+    else {
+        return false;
+    };
+
+    text.value = rest.trim_start().to_string();
+    if text.value.is_empty() {
+        paragraph.children.remove(0);
+    }
+
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::templates::{collect_md_headings, parse_md};
+
+    fn split(input: &str) -> color_eyre::Result<Vec<Slide>> {
+        let options = MarkdownOptions::default();
+        let mut ast = parse_md(input, &options)?;
+        let headings = collect_md_headings(&mut ast);
+
+        split_md_slides(&mut ast, &headings, &options)
+    }
+
+    #[test]
+    fn contents_are_split_into_slides() {
+        let slides = split(
+            "+++\n+++\n# Talk\n\nBy me\n\n---\n\nNo heading\n\n\
+            ## First [point][1]\n\nText\n\n---\n\n## Second\n\n---\n\n\
+            [1]: https://example.com",
+        )
+        .expect("Failed to split slides");
+
+        let slides: Vec<_> = slides
+            .iter()
+            .map(|slide| {
+                (slide.number, slide.title.as_str(), slide.content.as_str())
+            })
+            .collect();
+        assert_eq!(
+            slides,
+            vec![
+                (1, "Talk", "<h1 id=\"talk\">Talk</h1>\n<p>By me</p>"),
+                (2, "", "<p>No heading</p>"),
+                (
+                    3,
+                    "First point",
+                    "<h2 id=\"first-point\">First <a href=\"https://example.com\">\
+                    point</a></h2>\n<p>Text</p>"
+                ),
+                (4, "Second", "<h2 id=\"second\">Second</h2>"),
+            ]
+        );
+    }
+
+    #[test]
+    fn slides_have_attributes_and_notes() {
+        let slides = split(
+            "<!-- slide: class = \"title\", background = \"#000\" -->\n\n\
+            ## Intro\n\nHello\n\nNotes: Say *hi*.\n\nAnd smile.\n\n\
+            <!-- slide\nautoplay = true\n-->\n\n## End",
+        )
+        .expect("Failed to split slides");
+
+        assert_eq!(slides.len(), 2);
+        assert_eq!(slides[0].attributes["class"].as_str(), Some("title"));
+        assert_eq!(slides[0].attributes["background"].as_str(), Some("#000"));
+        assert_eq!(
+            slides[0].content,
+            "<h2 id=\"intro\">Intro</h2>\n<p>Hello</p>"
+        );
+        assert_eq!(
+            slides[0].notes,
+            "<p>Say <em>hi</em>.</p>\n<p>And smile.</p>"
+        );
+        assert_eq!(slides[1].attributes["autoplay"].as_bool(), Some(true));
+        assert_eq!(slides[1].notes, "");
+    }
+
+    #[test]
+    fn footnotes_are_numbered_across_the_deck() {
+        let slides = split(
+            "## One\n\nA[^a]\n\n## Two\n\nB[^b] and A[^a]\n\n\
+            [^a]: First.\n\n[^b]: Second.",
+        )
+        .expect("Failed to split slides");
+
+        assert_eq!(
+            slides[0].content,
+            "<h2 id=\"one\">One</h2>\n<p>A<sup><a href=\"#user-content-fn-slide-1-a\" \
+            id=\"user-content-fnref-slide-1-a\" data-footnote-ref=\"\" \
+            aria-describedby=\"slide-1-footnote-label\">1</a></sup></p>\n\
+            <section data-footnotes=\"\" class=\"footnotes\">\
+            <h2 id=\"slide-1-footnote-label\" class=\"sr-only\">Footnotes</h2>\n<ol>\n\
+            <li id=\"user-content-fn-slide-1-a\">\n<p>First. \
+            <a href=\"#user-content-fnref-slide-1-a\" data-footnote-backref=\"\" \
+            aria-label=\"Back to content\" class=\"data-footnote-backref\">↩</a></p>\n\
+            </li>\n</ol>\n</section>\n"
+        );
+        assert!(slides[1].content.contains(
+            "<a href=\"#user-content-fn-slide-2-b\" \
+            id=\"user-content-fnref-slide-2-b\" data-footnote-ref=\"\" \
+            aria-describedby=\"slide-2-footnote-label\">2</a>"
+        ));
+        assert!(slides[1].content.contains(
+            "<a href=\"#user-content-fn-slide-2-a\" \
+            id=\"user-content-fnref-slide-2-a\" data-footnote-ref=\"\" \
+            aria-describedby=\"slide-2-footnote-label\">3</a>"
+        ));
+        assert!(slides[1].content.contains(
+            "<ol start=\"2\">\n<li id=\"user-content-fn-slide-2-b\">"
+        ));
+    }
+
+    #[test]
+    fn invalid_attributes_are_errors() {
+        assert!(split("<!-- slide: class = -->\n\n# Title").is_err());
+    }
+}
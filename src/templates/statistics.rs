@@ -0,0 +1,49 @@
+/// Average reading speed, in words per minute, used to estimate reading times.
+pub const WORDS_PER_MINUTE: usize = 200;
+
+/// Returns the number of whitespace-separated words in `text`.
+#[must_use]
+pub fn count_words(text: &str) -> usize {
+    text.split_whitespace().count()
+}
+
+/// Returns the number of characters in `text`, not counting whitespace.
+#[must_use]
+pub fn count_chars(text: &str) -> usize {
+    text.chars().filter(|c| !c.is_whitespace()).count()
+}
+
+/// Returns the estimated minutes it takes to read `words` words, rounded up.
+#[must_use]
+pub const fn reading_time(words: usize) -> usize {
+    words.div_ceil(WORDS_PER_MINUTE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    #[rstest]
+    #[case::empty("", 0, 0)]
+    #[case::words("Some  words,\nover two\tlines", 5, 22)]
+    #[case::punctuation("Hello, world — again!", 4, 18)]
+    fn text_is_counted(
+        #[case] text: &str,
+        #[case] words: usize,
+        #[case] chars: usize,
+    ) {
+        assert_eq!(count_words(text), words);
+        assert_eq!(count_chars(text), chars);
+    }
+
+    #[rstest]
+    #[case::nothing(0, 0)]
+    #[case::a_few_words(10, 1)]
+    #[case::exactly_a_minute(200, 1)]
+    #[case::a_bit_more(201, 2)]
+    #[case::long_post(1150, 6)]
+    fn reading_time_rounds_up(#[case] words: usize, #[case] minutes: usize) {
+        assert_eq!(reading_time(words), minutes);
+    }
+}
@@ -0,0 +1,284 @@
+use super::{extraction::inline_text, trusted_html_node};
+use markdown::mdast::{Code, Html, Math, Node, Paragraph, Root, Text};
+
+/// Removes the `<!-- more -->` marker from a Markdown ast and returns an ast holding a summary
+/// of its contents.
+///
+/// The summary is made of the blocks before the marker. Without one, it's the first `words`
+/// words of the contents if set, or their first paragraph otherwise. Code, math and HTML blocks
+/// count their words too, and the summary stops before the first one that doesn't fit. Footnotes
+/// are left out of it.
+pub fn extract_md_summary(root: &mut Node, words: Option<usize>) -> Node {
+    let mut blocks = Vec::new();
+    let mut definitions = Vec::new();
+
+    separate_summary_marker(root);
+    if let Some(children) = root.children_mut() {
+        let marker = children.iter().position(is_summary_marker);
+        if let Some(index) = marker {
+            children.remove(index);
+        }
+
+        for (index, child) in children.iter().enumerate() {
+            match child {
+                Node::Definition(_) => definitions.push(child.clone()),
+                Node::Toml(_) | Node::Yaml(_) | Node::FootnoteDefinition(_) => {
+                }
+                _ if marker.is_none_or(|marker| index < marker) => {
+                    blocks.push(child.clone());
+                }
+                _ => {}
+            }
+        }
+
+        match (marker, words) {
+            (Some(_), _) => {}
+            (None, Some(words)) => {
+                truncate_words(&mut blocks, &mut { words }, true);
+            }
+            (None, None) => {
+                blocks.retain(|block| matches!(block, Node::Paragraph(_)));
+                blocks.truncate(1);
+            }
+        }
+    }
+
+    blocks.iter_mut().for_each(remove_footnote_references);
+    // Definitions aren't rendered, but keep the summary's link references working.
+    definitions.extend(blocks);
+
+    Node::Root(Root {
+        children: definitions,
+        position: None,
+    })
+}
+
+pub(super) fn is_summary_marker(node: &Node) -> bool {
+    match node {
+        Node::Html(html) => is_marker_text(&html.value),
+        Node::Paragraph(_) => is_marker_text(&inline_text(node)),
+        _ => false,
+    }
+}
+
+fn is_marker_text(text: &str) -> bool {
+    text.trim()
+        .strip_prefix("<!--")
+        .and_then(|text| text.strip_suffix("-->"))
+        .is_some_and(|text| text.trim() == "more")
+}
+
+/// Moves a `<!-- more -->` marker on the first line of a paragraph into a paragraph of its own.
+///
+/// Without inline HTML the marker is text, so a line right after it continues its paragraph.
+pub(super) fn separate_summary_marker(root: &mut Node) {
+    let Some(children) = root.children_mut() else {
+        return;
+    };
+    if children.iter().any(is_summary_marker) {
+        return;
+    }
+
+    for index in 0..children.len() {
+        let Node::Paragraph(paragraph) = &mut children[index] else {
+            continue;
+        };
+        let Some(Node::Text(text)) = paragraph.children.first_mut() else {
+            continue;
+        };
+        let Some((marker, rest)) = text.value.split_once('\n') else {
+            continue;
+        };
+        if !is_marker_text(marker) {
+            continue;
+        }
+
+        let marker = marker.trim().to_string();
+        text.value = rest.trim_start().to_string();
+        if text.value.is_empty() {
+            paragraph.children.remove(0);
+        }
+        children.insert(
+            index,
+            Node::Paragraph(Paragraph {
+                children: vec![Node::Text(Text {
+                    value: marker,
+                    position: None,
+                })],
+                position: None,
+            }),
+        );
+        return;
+    }
+}
+
+fn truncate_words(nodes: &mut Vec<Node>, remaining: &mut usize, flow: bool) {
+    for (index, node) in nodes.iter_mut().enumerate() {
+        if *remaining == 0 {
+            nodes.truncate(index);
+            return;
+        }
+
+        match node {
+            Node::Text(text) => {
+                let count = text.value.split_whitespace().count();
+                if count > *remaining {
+                    text.value = truncated_text(&text.value, *remaining);
+                    *remaining = 0;
+                } else {
+                    *remaining -= count;
+                }
+            }
+            Node::InlineCode(_) | Node::InlineMath(_) | Node::Image(_) => {
+                *remaining -= 1;
+            }
+            // Blocks that can't be cut short are kept whole if their words fit, and end the
+            // summary otherwise.
+            Node::Code(Code { value, .. })
+            | Node::Math(Math { value, .. })
+            | Node::Html(Html { value, .. })
+                if flow =>
+            {
+                let count = value.split_whitespace().count();
+                if count > *remaining {
+                    nodes.truncate(index);
+                    *remaining = 0;
+                    return;
+                }
+                *remaining -= count;
+            }
+            Node::Html(_) => {}
+            _ => {
+                // Generated HTML among blocks, such as an embedded page, is a block too.
+                let flow = matches!(
+                    node,
+                    Node::Blockquote(_)
+                        | Node::List(_)
+                        | Node::ListItem(_)
+                        | Node::MdxJsxFlowElement(_)
+                ) || (flow && trusted_html_node(node).is_some());
+                if let Some(children) = node.children_mut() {
+                    truncate_words(children, remaining, flow);
+                }
+            }
+        }
+    }
+}
+
+fn truncated_text(value: &str, words: usize) -> String {
+    let end = value
+        .split_whitespace()
+        .nth(words)
+        .map_or(value.len(), |word| {
+            word.as_ptr() as usize - value.as_ptr() as usize
+        });
+
+    format!("{}…", value[..end].trim_end())
+}
+
+fn remove_footnote_references(node: &mut Node) {
+    if let Some(children) = node.children_mut() {
+        children.retain(|child| !matches!(child, Node::FootnoteReference(_)));
+        children.iter_mut().for_each(remove_footnote_references);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::templates::{
+        MarkdownOptions, collect_md_headings, default_md_parse_options,
+        render_md_html,
+    };
+    use rstest::*;
+
+    fn summary(input: &str, inline_html: bool, words: Option<usize>) -> String {
+        let mut ast =
+            markdown::to_mdast(input, &default_md_parse_options(inline_html))
+                .expect("This should not fail");
+        let headings = collect_md_headings(&mut ast);
+        let summary = extract_md_summary(&mut ast, words);
+
+        render_md_html(&summary, &headings, &MarkdownOptions::default())
+    }
+
+    #[rstest]
+    #[case::escaped_html(false)]
+    #[case::inline_html(true)]
+    fn summary_ends_at_the_marker(#[case] inline_html: bool) {
+        let input = "# Title\n\nIntro with [a link][link].\n\n<!-- more -->\n\nRest\n\n\
+            [link]: https://example.com";
+
+        assert_eq!(
+            summary(input, inline_html, Some(1)),
+            "<h1 id=\"title\">Title</h1>\n\
+            <p>Intro with <a href=\"https://example.com\">a link</a>.</p>"
+        );
+    }
+
+    #[test]
+    fn summary_removes_the_marker_from_the_ast() {
+        let mut ast = markdown::to_mdast(
+            "Intro\n\n<!--more-->\n\nRest",
+            &default_md_parse_options(false),
+        )
+        .expect("This should not fail");
+        extract_md_summary(&mut ast, None);
+
+        assert_eq!(ast.children().map(Vec::len), Some(2));
+    }
+
+    #[rstest]
+    #[case::escaped_html(false)]
+    #[case::inline_html(true)]
+    fn summary_ends_at_a_marker_followed_by_text(#[case] inline_html: bool) {
+        let mut ast = markdown::to_mdast(
+            "Intro\n\n<!-- more -->\nText",
+            &default_md_parse_options(inline_html),
+        )
+        .expect("This should not fail");
+        let summary = extract_md_summary(&mut ast, None);
+
+        let options = MarkdownOptions::default();
+        assert_eq!(render_md_html(&summary, &[], &options), "<p>Intro</p>");
+        assert_eq!(
+            render_md_html(&ast, &[], &options),
+            "<p>Intro</p>\n<p>Text</p>"
+        );
+    }
+
+    #[test]
+    fn summary_falls_back_to_the_first_paragraph() {
+        let input = "# Title\n\nFirst *paragraph*.[^1]\n\nSecond paragraph.\n\n[^1]: Note";
+
+        assert_eq!(
+            summary(input, false, None),
+            "<p>First <em>paragraph</em>.</p>"
+        );
+    }
+
+    #[test]
+    fn summary_falls_back_to_a_number_of_words() {
+        let input = "Some *emphasized text* here.\n\nAnother paragraph.";
+
+        assert_eq!(
+            summary(input, false, Some(2)),
+            "<p>Some <em>emphasized…</em></p>"
+        );
+        assert_eq!(
+            summary(input, false, Some(5)),
+            "<p>Some <em>emphasized text</em> here.</p>\n<p>Another…</p>"
+        );
+    }
+
+    #[test]
+    fn summary_stops_before_blocks_with_too_many_words() {
+        let input = "```\na b c d e\n```\n\nx y z w v";
+
+        assert_eq!(summary(input, false, Some(3)), "");
+        assert_eq!(
+            summary(input, false, Some(7)),
+            "<pre><code>a b c d e\n</code></pre>\n<p>x y…</p>"
+        );
+    }
+}
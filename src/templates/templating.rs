@@ -0,0 +1,103 @@
+use super::{default_md_parse_options, parse_md_frontmatter};
+use color_eyre::eyre::Context;
+use markdown::mdast::Node;
+use std::collections::BTreeMap;
+use tera::Tera;
+
+/// Renders the body of a Markdown `&str` as a Tera template, leaving its frontmatter untouched.
+///
+/// The template has access to `variables` and to the fields of the TOML frontmatter, which take
+/// precedence over them.
+///
+/// # Errors
+///
+/// Returns an error if the frontmatter is not valid TOML or if the body fails to render.
+pub fn render_md_template(
+    input: &str,
+    variables: &BTreeMap<String, String>,
+) -> color_eyre::Result<String> {
+    let (ctx, body_start) = md_template_context(input, variables)?;
+
+    let (frontmatter, body) = input.split_at(body_start);
+    let body = Tera::one_off(body, &ctx, false)
+        .wrap_err("Failed to render Markdown body")?;
+
+    Ok(format!("{frontmatter}{body}"))
+}
+
+/// Returns the context a Markdown `&str`'s body is rendered with by [`render_md_template`], and
+/// the offset where its body starts.
+pub(super) fn md_template_context(
+    input: &str,
+    variables: &BTreeMap<String, String>,
+) -> color_eyre::Result<(tera::Context, usize)> {
+    let ast = markdown::to_mdast(input, &default_md_parse_options(false))
+        .expect("This should never fail");
+    let (frontmatter, body_start) = match ast.children().and_then(|c| c.first())
+    {
+        Some(Node::Toml(toml)) => (
+            parse_md_frontmatter(&toml.value)?,
+            toml.position
+                .as_ref()
+                .map_or(0, |position| position.end.offset),
+        ),
+        _ => (toml::Table::new(), 0),
+    };
+
+    let mut ctx = tera::Context::new();
+    for (key, value) in variables {
+        ctx.insert(key.clone(), value);
+    }
+    ctx.extend(tera::Context::from_serialize(&frontmatter)?);
+
+    Ok((ctx, body_start))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    #[test]
+    fn render_template_uses_frontmatter_and_variables() {
+        let input = "+++\ntitle = \"Post\"\nversion = \"2.0\"\ntags = [\"a\", \"b\"]\n+++\n\
+            # {{ title }} for {{ version }} by {{ author }}\n\n\
+            {% for tag in tags %}- {{ tag }}\n{% endfor %}";
+        let variables = BTreeMap::from([
+            ("version".to_string(), "1.0".to_string()),
+            ("author".to_string(), "Me".to_string()),
+        ]);
+
+        assert_eq!(
+            render_md_template(input, &variables)
+                .expect("Failed to render markdown body"),
+            "+++\ntitle = \"Post\"\nversion = \"2.0\"\ntags = [\"a\", \"b\"]\n+++\n\
+            # Post for 2.0 by Me\n\n- a\n- b\n"
+        );
+    }
+
+    #[rstest]
+    #[case::no_frontmatter("Version {{ version }}", "Version 1.0")]
+    #[case::raw_blocks(
+        "{% raw %}`{{ version }}`{% endraw %}",
+        "`{{ version }}`"
+    )]
+    fn render_template_works_without_frontmatter(
+        #[case] input: &str,
+        #[case] expected: &str,
+    ) {
+        let variables =
+            BTreeMap::from([("version".to_string(), "1.0".to_string())]);
+
+        assert_eq!(
+            render_md_template(input, &variables)
+                .expect("Failed to render markdown body"),
+            expected
+        );
+    }
+
+    #[test]
+    fn render_template_errors_on_invalid_templates() {
+        assert!(render_md_template("{{ missing }}", &BTreeMap::new()).is_err());
+    }
+}
@@ -0,0 +1,36 @@
+use camino::Utf8PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// A temporary directory of files for tests, removed when dropped.
+pub struct TempFiles {
+    pub root: Utf8PathBuf,
+}
+
+impl TempFiles {
+    /// Creates a new directory holding the given `(path, content)` files.
+    pub fn new(files: &[(&str, &str)]) -> Self {
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+
+        let root = Utf8PathBuf::from_path_buf(std::env::temp_dir())
+            .expect("Temporary directory is not UTF-8")
+            .join(format!(
+                "jango-{}-{}",
+                std::process::id(),
+                COUNT.fetch_add(1, Ordering::Relaxed)
+            ));
+        for (path, content) in files {
+            let path = root.join(path);
+            std::fs::create_dir_all(path.parent().expect("No parent"))
+                .expect("Failed to create directory");
+            std::fs::write(path, content).expect("Failed to write file");
+        }
+
+        Self { root }
+    }
+}
+
+impl Drop for TempFiles {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.root);
+    }
+}
@@ -0,0 +1,325 @@
+use super::{MarkdownOptions, extraction::inline_text, is_flow_content};
+use markdown::mdast::Node;
+
+/// Applies the typography transforms enabled in `options` to a Markdown ast.
+///
+/// They change the text that other steps read their syntax from, such as wikilinks and
+/// `{#id}` attributes, so they run after those. Paragraphs holding only an HTML comment, such as
+/// `<!-- more -->`, are left untouched.
+pub(super) fn apply_md_typography(root: &mut Node, options: &MarkdownOptions) {
+    if options.emoji {
+        replace_md_emoji(root);
+    }
+    if options.smart_punctuation {
+        smarten_md_punctuation(root);
+    }
+}
+
+/// Replaces the `:name:` emoji shortcodes in the text of a Markdown ast, such as `:rocket:`, with
+/// their emoji. Unknown shortcodes, the contents of code and autolinks are left untouched.
+pub fn replace_md_emoji(root: &mut Node) {
+    if is_comment(root) || is_autolink(root) {
+        return;
+    }
+    if let Node::Text(text) = root
+        && text.value.contains(':')
+    {
+        text.value = replace_emoji(&text.value);
+    }
+
+    for child in root.children_mut().into_iter().flatten() {
+        replace_md_emoji(child);
+    }
+}
+
+fn replace_emoji(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find(':') {
+        output.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let end = after
+            .find(|c: char| !(c.is_ascii_alphanumeric() || "_+-".contains(c)))
+            .unwrap_or(after.len());
+
+        if after[end..].starts_with(':')
+            && let Some(emoji) = emojis::get_by_shortcode(&after[..end])
+        {
+            output.push_str(emoji.as_str());
+            rest = &after[end + 1..];
+        } else {
+            output.push(':');
+            rest = after;
+        }
+    }
+    output.push_str(rest);
+
+    output
+}
+
+/// Replaces the straight quotes of the text of a Markdown ast with curly ones, `--` and `---`
+/// with en and em dashes and `...` with an ellipsis. The contents of code and autolinks are left
+/// untouched.
+///
+/// Whether a quote opens or closes depends on the character before it, even when it belongs to
+/// another node, as in `"*emphasis*"`.
+pub fn smarten_md_punctuation(root: &mut Node) {
+    smarten(root, &mut None);
+}
+
+fn smarten(node: &mut Node, previous: &mut Option<char>) {
+    match node {
+        Node::Text(text) => text.value = smarten_text(&text.value, previous),
+        Node::InlineCode(code) => *previous = code.value.chars().last(),
+        Node::InlineMath(math) => *previous = math.value.chars().last(),
+        Node::Image(image) => *previous = image.alt.chars().last(),
+        Node::Break(_) => *previous = None,
+        node if is_comment(node) => *previous = None,
+        node if is_autolink(node) => {
+            *previous = node.to_string().chars().last();
+        }
+        node if is_flow_content(node)
+            || matches!(
+                node,
+                Node::Root(_) | Node::ListItem(_) | Node::TableCell(_)
+            ) =>
+        {
+            *previous = None;
+            for child in node.children_mut().into_iter().flatten() {
+                smarten(child, previous);
+            }
+            *previous = None;
+        }
+        node => {
+            for child in node.children_mut().into_iter().flatten() {
+                smarten(child, previous);
+            }
+        }
+    }
+}
+
+fn smarten_text(text: &str, previous: &mut Option<char>) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut output = String::with_capacity(text.len());
+    let mut index = 0;
+    while index < chars.len() {
+        let rest = &chars[index..];
+        let (replacement, length) = match rest {
+            ['-', '-', '-', ..] => ('—', 3),
+            ['-', '-', ..] => ('–', 2),
+            ['.', '.', '.', ..] => ('…', 3),
+            ['"', ..] if opens_quote(*previous) => ('“', 1),
+            ['"', ..] => ('”', 1),
+            ['\'', ..] if opens_quote(*previous) => ('‘', 1),
+            ['\'', ..] => ('’', 1),
+            [c, ..] => (*c, 1),
+            [] => unreachable!("The index is always inside the text"),
+        };
+
+        output.push(replacement);
+        *previous = Some(replacement);
+        index += length;
+    }
+
+    output
+}
+
+/// Whether `node` is a paragraph holding only an HTML comment, which is how comments are parsed
+/// when inline HTML is disabled.
+fn is_comment(node: &Node) -> bool {
+    if !matches!(node, Node::Paragraph(_)) {
+        return false;
+    }
+
+    let text = inline_text(node);
+    let text = text.trim();
+    text.starts_with("<!--") && text.ends_with("-->")
+}
+
+/// Whether `node` is a link showing its own URL, such as `<https://example.com>` or a bare URL,
+/// whose text has to keep matching it.
+fn is_autolink(node: &Node) -> bool {
+    let Node::Link(link) = node else {
+        return false;
+    };
+    let [Node::Text(text)] = link.children.as_slice() else {
+        return false;
+    };
+
+    link.url
+        .strip_suffix(text.value.as_str())
+        .is_some_and(|scheme| matches!(scheme, "" | "http://" | "mailto:"))
+}
+
+fn opens_quote(previous: Option<char>) -> bool {
+    previous.is_none_or(|c| c.is_whitespace() || "([{“‘—–-/".contains(c))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::templates::{
+        MarkdownOptions, Vault, parse_md, parse_md_content, process_md_file,
+        resolve_md, test_files::TempFiles,
+    };
+    use rstest::*;
+
+    fn render(input: &str, options: &MarkdownOptions) -> String {
+        parse_md_content(input, options)
+            .expect("Failed to parse markdown input")
+    }
+
+    fn typographic() -> MarkdownOptions {
+        MarkdownOptions {
+            emoji: true,
+            smart_punctuation: true,
+            ..MarkdownOptions::default()
+        }
+    }
+
+    #[rstest]
+    #[case::shortcodes("Shipped :rocket: :+1:", "<p>Shipped 🚀 👍</p>")]
+    #[case::unknown(
+        "Time 10:30 :not_an_emoji: :",
+        "<p>Time 10:30 :not_an_emoji: :</p>"
+    )]
+    #[case::code(
+        "`:rocket:` **:tada:**",
+        "<p><code>:rocket:</code> <strong>🎉</strong></p>"
+    )]
+    #[case::autolinks(
+        "https://x.com/:rocket:/ <https://x.com/:tada:> [:tada:](https://x.com)",
+        "<p><a href=\"https://x.com/:rocket:/\">https://x.com/:rocket:/</a> \
+        <a href=\"https://x.com/:tada:\">https://x.com/:tada:</a> \
+        <a href=\"https://x.com\">🎉</a></p>"
+    )]
+    fn emoji_shortcodes_are_replaced(
+        #[case] input: &str,
+        #[case] expected: &str,
+    ) {
+        let options = MarkdownOptions {
+            emoji: true,
+            ..MarkdownOptions::default()
+        };
+
+        assert_eq!(render(input, &options), expected);
+    }
+
+    #[rstest]
+    #[case::quotes(
+        "\"Hello,\" she said. 'It's fine.'",
+        "<p>“Hello,” she said. ‘It’s fine.’</p>"
+    )]
+    #[case::dashes_and_ellipses(
+        "Pages 1--5 --- and more...",
+        "<p>Pages 1–5 — and more…</p>"
+    )]
+    #[case::across_nodes(
+        "\"*emphasis*\" and (\"[link](a.html)\")",
+        "<p>“<em>emphasis</em>” and (“<a href=\"a.html\">link</a>”)</p>"
+    )]
+    #[case::code(
+        "`\"code\" -- ...`\n\n```\n'block'\n```",
+        "<p><code>&quot;code&quot; -- ...</code></p>\n\
+        <pre><code>'block'\n</code></pre>"
+    )]
+    #[case::autolinks(
+        "See www.x.com/a--b... or <https://x.com/'c'>, \"a@b.com\"",
+        "<p>See <a href=\"http://www.x.com/a--b\">www.x.com/a--b</a>… or \
+        <a href=\"https://x.com/'c'\">https://x.com/'c'</a>, \
+        “<a href=\"mailto:a@b.com\">a@b.com</a>”</p>"
+    )]
+    fn punctuation_is_smartened(#[case] input: &str, #[case] expected: &str) {
+        let options = MarkdownOptions {
+            smart_punctuation: true,
+            ..MarkdownOptions::default()
+        };
+
+        assert_eq!(render(input, &options), expected);
+    }
+
+    #[test]
+    fn transforms_are_disabled_by_default() {
+        assert_eq!(
+            render("\"Go\" :rocket: --", &MarkdownOptions::default()),
+            "<p>&quot;Go&quot; :rocket: --</p>"
+        );
+    }
+
+    #[test]
+    fn headings_keep_their_ids_and_get_transformed_text() {
+        let mut ast =
+            parse_md("+++\n+++\n# \"Go\" :rocket: {#go--now}", &typographic())
+                .expect("Failed to parse markdown input");
+        let resolved =
+            resolve_md(&mut ast, &toml::Table::new(), &typographic())
+                .expect("Failed to resolve markdown input");
+
+        assert_eq!(resolved.headings[0].id, "go--now");
+        assert_eq!(resolved.headings[0].text, "“Go” 🚀");
+    }
+
+    #[rstest]
+    #[case::own_paragraph("\n\n")]
+    #[case::followed_by_text("\n")]
+    fn summary_markers_are_kept(#[case] separator: &str) {
+        let document = process_md_file(
+            &format!(
+                "+++\n+++\n\"Intro\" :rocket:\n\n<!-- more -->{separator}The rest..."
+            ),
+            &typographic(),
+        )
+        .expect("Failed to parse markdown input");
+
+        assert_eq!(document.summary, "<p>“Intro” 🚀</p>");
+        assert_eq!(document.content, "<p>“Intro” 🚀</p>\n<p>The rest…</p>");
+    }
+
+    #[test]
+    fn slide_attributes_are_kept() {
+        let options = MarkdownOptions {
+            slides: true,
+            ..typographic()
+        };
+        let document = process_md_file(
+            "+++\n+++\n<!-- slide: class = \"x\" -->\n\n# \"One\"\n\n\
+            <!-- more -->\n\n---\n\n# Two",
+            &options,
+        )
+        .expect("Failed to parse markdown input");
+
+        assert_eq!(
+            document.slides[0].attributes.get("class"),
+            Some(&toml::Value::String("x".to_string()))
+        );
+        assert_eq!(document.slides[0].content, "<h1 id=\"one\">“One”</h1>");
+        assert_eq!(
+            document.content,
+            "<h1 id=\"one\">“One”</h1>\n<hr />\n<h1 id=\"two\">Two</h1>"
+        );
+        assert_eq!(document.summary, "<h1 id=\"one\">“One”</h1>");
+    }
+
+    #[test]
+    fn wikilinks_are_resolved_first() {
+        let vault = TempFiles::new(&[("index.md", ""), ("Don't Panic.md", "")]);
+        let options = MarkdownOptions {
+            vault: Some(
+                Vault::load(&vault.root, &vault.root.join("index.md"))
+                    .expect("Failed to load vault"),
+            ),
+            ..typographic()
+        };
+        let document = process_md_file(
+            "+++\n+++\n[[Don't Panic]] and [[Don't Panic|\"it\"]]",
+            &options,
+        )
+        .expect("Failed to parse markdown input");
+
+        assert!(document.warnings.is_empty());
+        assert_eq!(
+            document.content,
+            "<p><a href=\"Don't%20Panic.html\">Don’t Panic</a> and \
+            <a href=\"Don't%20Panic.html\">“it”</a></p>"
+        );
+    }
+}
@@ -0,0 +1,803 @@
+use super::{
+    Includes, MarkdownHeading, MarkdownOptions, apply_md_typography,
+    collect_md_headings, parse_md, rebase_md_links,
+    rendering::{FootnoteScope, render_scoped},
+    rewrite_md_links, slugify, trusted_html,
+};
+use camino::{Utf8Path, Utf8PathBuf};
+use color_eyre::eyre::{Context, eyre};
+use markdown::mdast::{Image, Link, Node, Paragraph, Text};
+
+/// A directory of Markdown files that can link to and embed each other with `[[wikilinks]]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Vault {
+    root: Utf8PathBuf,
+    files: Vec<Utf8PathBuf>,
+    current: Utf8PathBuf,
+}
+
+impl Vault {
+    /// Creates a vault from the files inside `root`, skipping hidden files and directories.
+    /// Links are resolved for the page `current`, which must be inside `root`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `root` can't be read or if `current` is not inside it.
+    pub fn load(
+        root: &Utf8Path,
+        current: &Utf8Path,
+    ) -> color_eyre::Result<Self> {
+        let root = root
+            .canonicalize_utf8()
+            .wrap_err_with(|| format!("Failed to open vault `{root}`"))?;
+        let current = current
+            .canonicalize_utf8()?
+            .strip_prefix(&root)
+            .map_err(|_| eyre!("`{current}` is not inside the vault `{root}`"))?
+            .to_path_buf();
+
+        let mut files = Vec::new();
+        collect_files(&root, Utf8Path::new(""), &mut files)?;
+        files.sort();
+
+        Ok(Self {
+            root,
+            files,
+            current,
+        })
+    }
+
+    /// Finds the file a wikilink points to, either by its path relative to the vault or by its
+    /// name. Markdown files can be referenced without their extension.
+    fn resolve(&self, target: &str) -> Option<&Utf8Path> {
+        let target = target.trim().to_lowercase();
+        let matches = |path: &Utf8Path| {
+            let path = path.as_str().to_lowercase();
+            let without_extension =
+                path.strip_suffix(".md").unwrap_or(&path).to_string();
+
+            [path, without_extension].iter().any(|candidate| {
+                *candidate == target
+                    || candidate
+                        .rsplit_once('/')
+                        .is_some_and(|(_, name)| name == target)
+            })
+        };
+
+        self.files
+            .iter()
+            .find(|file| matches(file))
+            .map(Utf8PathBuf::as_path)
+    }
+
+    /// Returns the URL of the output for `file`, relative to the output of the current page.
+    fn url(&self, file: &Utf8Path) -> String {
+        let output = if file.extension() == Some("md") {
+            file.with_extension("html")
+        } else {
+            file.to_path_buf()
+        };
+
+        self.relative_path(&output)
+    }
+
+    /// Returns the path of `path`, relative to the vault, from the directory of the current page.
+    fn relative_path(&self, path: &Utf8Path) -> String {
+        let from: Vec<_> = self
+            .current
+            .parent()
+            .into_iter()
+            .flat_map(Utf8Path::components)
+            .collect();
+        let to: Vec<_> = path.components().collect();
+        let common = from
+            .iter()
+            .zip(&to)
+            .take_while(|(from, to)| from == to)
+            .count();
+
+        std::iter::repeat_n("..", from.len() - common)
+            .chain(to[common..].iter().map(camino::Utf8Component::as_str))
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+}
+
+fn collect_files(
+    root: &Utf8Path,
+    directory: &Utf8Path,
+    files: &mut Vec<Utf8PathBuf>,
+) -> color_eyre::Result<()> {
+    for entry in root.join(directory).read_dir_utf8()? {
+        let entry = entry?;
+        if entry.file_name().starts_with('.') {
+            continue;
+        }
+
+        let path = directory.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            collect_files(root, &path, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+/// Replaces the wikilinks of a Markdown ast with links to the output of the vault's pages.
+///
+/// Handles `[[Page]]`, `[[Page#Heading]]`, `[[#Heading]]`, `[[Page|alias]]` and `![[Page]]`,
+/// and returns a warning for each wikilink that can't be resolved. An embed of a Markdown page is
+/// replaced by its rendered content, or by the section under one of its headings, with the ids
+/// of its headings and footnotes prefixed by `embed-1-`, `embed-2-`, etc. The paragraph around
+/// it is split in two, as block content can't go inside a paragraph. An embed that can't be split
+/// out, such as one in a heading or in emphasis, becomes a link with a warning instead. An embed
+/// of a non-Markdown file becomes an image. Links to pages that aren't in the vault are left as
+/// plain text. Does nothing when `options` has no vault.
+///
+/// Embedded pages get their links, heading ids and typography transforms, but their figures,
+/// citations and cross-references are left as written: those are numbered and resolved across
+/// the page being compiled, which only sees embeds as finished HTML.
+///
+/// # Errors
+///
+/// Returns an error if an embedded page can't be read or if pages embed each other in a cycle.
+pub fn resolve_md_wikilinks(
+    root: &mut Node,
+    options: &MarkdownOptions,
+) -> color_eyre::Result<Vec<String>> {
+    let Some(vault) = &options.vault else {
+        return Ok(Vec::new());
+    };
+
+    let mut resolver = WikiLinkResolver {
+        vault,
+        options,
+        embedding: vec![vault.current.clone()],
+        embeds: 0,
+        warnings: Vec::new(),
+    };
+    resolver.resolve(root)?;
+
+    Ok(resolver.warnings)
+}
+
+struct WikiLinkResolver<'a> {
+    vault: &'a Vault,
+    options: &'a MarkdownOptions,
+    embedding: Vec<Utf8PathBuf>,
+    /// Number of pages embedded so far, which tells their ids apart.
+    embeds: usize,
+    warnings: Vec<String>,
+}
+
+enum Segment<'a> {
+    Text(&'a str),
+    WikiLink { target: &'a str, embed: bool },
+}
+
+impl WikiLinkResolver<'_> {
+    fn resolve(&mut self, node: &mut Node) -> color_eyre::Result<()> {
+        let Some(children) = node.children_mut() else {
+            return Ok(());
+        };
+
+        let mut resolved = Vec::with_capacity(children.len());
+        for mut child in std::mem::take(children) {
+            match child {
+                Node::Text(text) => {
+                    for segment in split_wikilinks(&text.value) {
+                        resolved.push(self.segment(&segment));
+                    }
+                }
+                Node::Paragraph(paragraph) => {
+                    resolved.extend(self.paragraph(paragraph)?);
+                }
+                Node::Code(_) | Node::InlineCode(_) | Node::Html(_) => {
+                    resolved.push(child);
+                }
+                _ => {
+                    self.resolve(&mut child)?;
+                    resolved.push(child);
+                }
+            }
+        }
+
+        if let Some(children) = node.children_mut() {
+            *children = resolved;
+        }
+
+        Ok(())
+    }
+
+    /// Resolves the wikilinks of a paragraph, splitting it around the embeds of Markdown pages.
+    fn paragraph(
+        &mut self,
+        mut paragraph: Paragraph,
+    ) -> color_eyre::Result<Vec<Node>> {
+        let mut nodes = Vec::new();
+        let mut children = Vec::new();
+        for mut child in std::mem::take(&mut paragraph.children) {
+            let Node::Text(text) = &child else {
+                self.resolve(&mut child)?;
+                children.push(child);
+                continue;
+            };
+
+            for segment in split_wikilinks(&text.value) {
+                if let Segment::WikiLink {
+                    target,
+                    embed: true,
+                } = segment
+                    && let Some(file) = self.embedded_page(target)
+                {
+                    push_paragraph(&mut nodes, std::mem::take(&mut children));
+                    nodes.push(self.embed(target, file)?);
+                } else {
+                    children.push(self.segment(&segment));
+                }
+            }
+        }
+
+        if nodes.is_empty() {
+            paragraph.children = children;
+            nodes.push(Node::Paragraph(paragraph));
+        } else {
+            push_paragraph(&mut nodes, children);
+        }
+
+        Ok(nodes)
+    }
+
+    /// Returns the Markdown page that an embed's `target` points to, if it's one.
+    fn embedded_page(&self, target: &str) -> Option<Utf8PathBuf> {
+        let link = target.split('|').next().unwrap_or(target);
+        let page = link.split_once('#').map_or(link, |(page, _)| page);
+
+        self.vault
+            .resolve(page)
+            .filter(|file| file.extension() == Some("md"))
+            .map(Utf8Path::to_path_buf)
+    }
+
+    fn segment(&mut self, segment: &Segment) -> Node {
+        let (target, embed) = match segment {
+            Segment::Text(text) => return text_node(text),
+            Segment::WikiLink { target, embed } => (*target, *embed),
+        };
+
+        let (link, alias) = target.split_once('|').unwrap_or((target, ""));
+        let (page, heading) = link.split_once('#').unwrap_or((link, ""));
+        let label = match (alias, heading) {
+            ("", "") => page.to_string(),
+            ("", heading) if page.is_empty() => heading.to_string(),
+            ("", heading) => format!("{page} > {heading}"),
+            (alias, _) => alias.to_string(),
+        };
+
+        if page.is_empty() && !heading.is_empty() && !embed {
+            return Node::Link(Link {
+                children: vec![text_node(&label)],
+                url: format!("#{}", slugify(heading)),
+                title: None,
+                position: None,
+            });
+        }
+
+        let Some(file) = self.vault.resolve(page) else {
+            self.warnings
+                .push(format!("Unresolved wikilink `[[{target}]]`"));
+            return text_node(&label);
+        };
+
+        let mut url = self.vault.url(file);
+        if !heading.is_empty() {
+            url = format!("{url}#{}", slugify(heading));
+        }
+
+        if embed && file.extension() != Some("md") {
+            let alt = if alias.is_empty() {
+                file.file_stem().unwrap_or_default()
+            } else {
+                alias
+            };
+
+            return Node::Image(Image {
+                url,
+                alt: alt.to_string(),
+                title: None,
+                position: None,
+            });
+        }
+        if embed {
+            self.warnings.push(format!(
+                "Embed `![[{target}]]` can't be split out of the content \
+                around it, linking to it instead"
+            ));
+        }
+
+        Node::Link(Link {
+            children: vec![text_node(&label)],
+            url,
+            title: None,
+            position: None,
+        })
+    }
+
+    fn embed(
+        &mut self,
+        target: &str,
+        file: Utf8PathBuf,
+    ) -> color_eyre::Result<Node> {
+        let link = target.split('|').next().unwrap_or(target);
+        let heading = link.split_once('#').map_or("", |(_, heading)| heading);
+
+        if self.embedding.contains(&file) {
+            return Err(eyre!(
+                "`{file}` embeds itself, directly or through other pages"
+            ));
+        }
+
+        let input = std::fs::read_to_string(self.vault.root.join(&file))
+            .wrap_err_with(|| {
+                format!("Failed to read embedded page `{file}`")
+            })?;
+        // Includes are relative to the embedded page, not to the page embedding it.
+        let options = MarkdownOptions {
+            includes: self.options.includes.as_ref().map(|includes| Includes {
+                current: self.vault.root.join(&file),
+                root: includes.root.clone(),
+            }),
+            ..self.options.clone()
+        };
+        let mut ast = parse_md(&input, &options)?;
+        // Relative URLs are relative to the embedded page, not to the page embedding it.
+        rebase_md_links(
+            &mut ast,
+            &self.vault.relative_path(file.parent().unwrap_or(&file)),
+        );
+
+        self.embedding.push(file);
+        self.resolve(&mut ast)?;
+        self.embedding.pop();
+        rewrite_md_links(&mut ast, self.options);
+
+        let mut headings = collect_md_headings(&mut ast);
+        apply_md_typography(&mut ast, self.options);
+        if !heading.is_empty()
+            && !keep_md_section(&mut ast, &headings, &slugify(heading))
+        {
+            self.warnings.push(format!(
+                "Heading `{heading}` of wikilink `![[{target}]]` not found, \
+                embedding the whole page"
+            ));
+        }
+
+        // The page is rendered on its own, so its ids could clash with the host page's.
+        self.embeds += 1;
+        let scope = FootnoteScope {
+            prefix: format!("embed-{}-", self.embeds),
+            offset: 0,
+        };
+        for heading in &mut headings {
+            heading.id.insert_str(0, &scope.prefix);
+        }
+        let (mut content, footnotes) =
+            render_scoped(&ast, &headings, self.options, &scope);
+        footnotes.append_to(&mut content);
+
+        Ok(trusted_html(format!(
+            "<div class=\"embed\">\n{}\n</div>",
+            content.trim_end()
+        )))
+    }
+}
+
+/// Keeps only the top-level heading of a Markdown ast with the id `id`, the blocks under it up to
+/// the next heading of the same or a higher level and the definitions they may reference.
+/// Returns whether the heading was found.
+fn keep_md_section(
+    root: &mut Node,
+    headings: &[MarkdownHeading],
+    id: &str,
+) -> bool {
+    let Some(offset) = headings
+        .iter()
+        .find(|heading| heading.id == id)
+        .and_then(|heading| heading.offset)
+    else {
+        return false;
+    };
+    let Some(children) = root.children_mut() else {
+        return false;
+    };
+    let Some((start, depth)) =
+        children
+            .iter()
+            .enumerate()
+            .find_map(|(index, child)| match child {
+                Node::Heading(heading)
+                    if heading.position.as_ref().is_some_and(|position| {
+                        position.start.offset == offset
+                    }) =>
+                {
+                    Some((index, heading.depth))
+                }
+                _ => None,
+            })
+    else {
+        return false;
+    };
+
+    let end = children[start + 1..]
+        .iter()
+        .position(
+            |child| matches!(child, Node::Heading(next) if next.depth <= depth),
+        )
+        .map_or(children.len(), |end| start + 1 + end);
+
+    let mut index = 0;
+    children.retain(|child| {
+        let keep = (start..end).contains(&index)
+            || matches!(
+                child,
+                Node::Definition(_) | Node::FootnoteDefinition(_)
+            );
+        index += 1;
+        keep
+    });
+
+    true
+}
+
+fn text_node(value: &str) -> Node {
+    Node::Text(Text {
+        value: value.to_string(),
+        position: None,
+    })
+}
+
+fn split_wikilinks(text: &str) -> Vec<Segment<'_>> {
+    let mut segments = Vec::new();
+    let mut rest = text;
+
+    while let Some(start) = rest.find("[[") {
+        let Some(length) = rest[start + 2..].find("]]") else {
+            break;
+        };
+        let target = &rest[start + 2..start + 2 + length];
+        if target.trim().is_empty() || target.contains('[') {
+            segments.push(Segment::Text(&rest[..start + 2]));
+            rest = &rest[start + 2..];
+            continue;
+        }
+
+        let embed = rest[..start].ends_with('!');
+        let before = &rest[..start - usize::from(embed)];
+        if !before.is_empty() {
+            segments.push(Segment::Text(before));
+        }
+        segments.push(Segment::WikiLink { target, embed });
+        rest = &rest[start + 2 + length + 2..];
+    }
+
+    if !rest.is_empty() {
+        segments.push(Segment::Text(rest));
+    }
+
+    segments
+}
+
+/// Pushes the part of a paragraph before or after an embed, without the whitespace and line
+/// breaks around the embed, unless it's empty.
+fn push_paragraph(nodes: &mut Vec<Node>, mut children: Vec<Node>) {
+    if let Some(Node::Text(text)) = children.first_mut() {
+        text.value = text.value.trim_start().to_string();
+    }
+    if let Some(Node::Text(text)) = children.last_mut() {
+        text.value = text.value.trim_end().to_string();
+    }
+    children.retain(
+        |child| !matches!(child, Node::Text(text) if text.value.is_empty()),
+    );
+    if matches!(children.last(), Some(Node::Break(_))) {
+        children.pop();
+    }
+    if matches!(children.first(), Some(Node::Break(_))) {
+        children.remove(0);
+    }
+
+    if !children.is_empty() {
+        nodes.push(Node::Paragraph(Paragraph {
+            children,
+            position: None,
+        }));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::templates::{render_md_html, test_files::TempFiles};
+    use rstest::*;
+
+    fn options(vault: &TempFiles, current: &str) -> MarkdownOptions {
+        MarkdownOptions {
+            vault: Some(
+                Vault::load(&vault.root, &vault.root.join(current))
+                    .expect("Failed to load vault"),
+            ),
+            ..MarkdownOptions::default()
+        }
+    }
+
+    fn render(
+        input: &str,
+        options: &MarkdownOptions,
+    ) -> color_eyre::Result<String> {
+        let mut ast = parse_md(input, options)?;
+        resolve_md_wikilinks(&mut ast, options)?;
+        rewrite_md_links(&mut ast, options);
+        let headings = collect_md_headings(&mut ast);
+
+        Ok(render_md_html(&ast, &headings, options))
+    }
+
+    #[fixture]
+    fn vault() -> TempFiles {
+        TempFiles::new(&[
+            ("index.md", ""),
+            ("notes/Page Name.md", "# Page\n\nSome *page* content."),
+            (
+                "Chapters.md",
+                "# One\n\nFirst.\n\n## Two\n\nSecond.\n\n# Three\n\nThird.",
+            ),
+            ("notes/deep/Other.md", "Other\n\n![[Page Name]]"),
+            ("notes/Snippets.md", "<!-- include: snippet.md -->"),
+            (
+                "Noted.md",
+                "# Notes\n\nEmbedded[^a]\n\n[^a]: Embedded note.",
+            ),
+            ("notes/snippet.md", "Included."),
+            (
+                "notes/Illustrated.md",
+                "![pic](pic.png) [page](Page%20Name.md) [[Chapters]]",
+            ),
+            ("images/pic.png", ""),
+            ("Loop.md", "![[Loop]]"),
+            ("Spiral.md", "Around ![[Spiral]] again"),
+            ("Ping.md", "![[Pong]]"),
+            ("Pong.md", "Back to ![[Ping]] again"),
+            (".obsidian/Hidden.md", ""),
+        ])
+    }
+
+    #[rstest]
+    fn wikilinks_resolve_to_output_paths(vault: TempFiles) {
+        let result = render(
+            "See [[Page Name]], [[Other|the other]] and [[Page Name#Some Heading]].",
+            &options(&vault, "index.md"),
+        )
+        .expect("Failed to resolve wikilinks");
+
+        assert_eq!(
+            result,
+            "<p>See <a href=\"notes/Page%20Name.html\">Page Name</a>, \
+            <a href=\"notes/deep/Other.html\">the other</a> and \
+            <a href=\"notes/Page%20Name.html#some-heading\">Page Name &gt; Some Heading</a>.</p>"
+        );
+    }
+
+    #[rstest]
+    fn wikilinks_to_headings_of_the_current_page(vault: TempFiles) {
+        let result = render("[[#A Heading]]", &options(&vault, "index.md"))
+            .expect("Failed to resolve wikilinks");
+
+        assert_eq!(result, "<p><a href=\"#a-heading\">A Heading</a></p>");
+    }
+
+    #[rstest]
+    fn wikilinks_are_rebased_on_the_base_url(vault: TempFiles) {
+        let options = MarkdownOptions {
+            base_url: Some("/site".to_string()),
+            ..options(&vault, "index.md")
+        };
+
+        let result = render("[[Other]] ![[pic.png]]", &options)
+            .expect("Failed to resolve wikilinks");
+
+        assert_eq!(
+            result,
+            "<p><a href=\"/site/notes/deep/Other.html\">Other</a> \
+            <img src=\"/site/images/pic.png\" alt=\"pic\" /></p>"
+        );
+    }
+
+    #[rstest]
+    fn wikilinks_are_relative_to_the_current_page(vault: TempFiles) {
+        let result = render(
+            "[[index]] [[notes/Page Name]]",
+            &options(&vault, "notes/deep/Other.md"),
+        )
+        .expect("Failed to resolve wikilinks");
+
+        assert_eq!(
+            result,
+            "<p><a href=\"../../index.html\">index</a> \
+            <a href=\"../Page%20Name.html\">notes/Page Name</a></p>"
+        );
+    }
+
+    #[rstest]
+    fn wikilinks_to_missing_pages_are_left_as_text(vault: TempFiles) {
+        let options = options(&vault, "index.md");
+        let mut ast =
+            parse_md("[[Missing|alias]] [[Hidden]] `[[code]]`", &options)
+                .expect("Failed to parse markdown input");
+
+        let warnings = resolve_md_wikilinks(&mut ast, &options)
+            .expect("Failed to resolve wikilinks");
+        let result = render_md_html(&ast, &[], &options);
+
+        assert_eq!(result, "<p>alias Hidden <code>[[code]]</code></p>");
+        assert_eq!(
+            warnings,
+            [
+                "Unresolved wikilink `[[Missing|alias]]`",
+                "Unresolved wikilink `[[Hidden]]`"
+            ]
+        );
+    }
+
+    #[rstest]
+    fn embeds_transclude_rendered_pages(vault: TempFiles) {
+        let result =
+            render("Intro\n\n![[Other]]", &options(&vault, "index.md"))
+                .expect("Failed to resolve wikilinks");
+
+        assert_eq!(
+            result,
+            "<p>Intro</p>\n<div class=\"embed\">\n<p>Other</p>\n\
+            <div class=\"embed\">\n<h1 id=\"embed-1-page\">Page</h1>\n\
+            <p>Some <em>page</em> content.</p>\n</div>\n</div>"
+        );
+    }
+
+    #[rstest]
+    fn embeds_split_the_paragraph_around_them(vault: TempFiles) {
+        let result = render(
+            "Before ![[Page Name]] after.",
+            &options(&vault, "index.md"),
+        )
+        .expect("Failed to resolve wikilinks");
+
+        assert_eq!(
+            result,
+            "<p>Before</p>\n<div class=\"embed\">\n<h1 id=\"embed-1-page\">Page</h1>\n\
+            <p>Some <em>page</em> content.</p>\n</div>\n<p>after.</p>"
+        );
+    }
+
+    #[rstest]
+    fn embeds_inside_other_content_become_links(vault: TempFiles) {
+        let options = options(&vault, "index.md");
+        let mut ast = parse_md("*See ![[Page Name]]*", &options)
+            .expect("Failed to parse markdown input");
+
+        let warnings = resolve_md_wikilinks(&mut ast, &options)
+            .expect("Failed to resolve wikilinks");
+        let result = render_md_html(&ast, &[], &options);
+
+        assert_eq!(
+            result,
+            "<p><em>See <a href=\"notes/Page%20Name.html\">Page Name</a></em></p>"
+        );
+        assert_eq!(
+            warnings,
+            [
+                "Embed `![[Page Name]]` can't be split out of the content around \
+            it, linking to it instead"
+            ]
+        );
+    }
+
+    #[rstest]
+    fn embeds_of_headings_transclude_their_sections(vault: TempFiles) {
+        let result = render("![[Chapters#One]]", &options(&vault, "index.md"))
+            .expect("Failed to resolve wikilinks");
+
+        assert_eq!(
+            result,
+            "<div class=\"embed\">\n<h1 id=\"embed-1-one\">One</h1>\n<p>First.</p>\n\
+            <h2 id=\"embed-1-two\">Two</h2>\n<p>Second.</p>\n</div>"
+        );
+    }
+
+    #[rstest]
+    fn embeds_have_their_own_ids(vault: TempFiles) {
+        let result = render(
+            "# Notes\n\nHost[^a]\n\n![[Noted]]\n\n[^a]: Host note.",
+            &options(&vault, "index.md"),
+        )
+        .expect("Failed to resolve wikilinks");
+
+        assert!(result.starts_with("<h1 id=\"notes\">Notes</h1>"));
+        assert!(result.contains("<h1 id=\"embed-1-notes\">Notes</h1>"));
+        assert!(result.contains(
+            "<a href=\"#user-content-fn-embed-1-a\" \
+            id=\"user-content-fnref-embed-1-a\" data-footnote-ref=\"\" \
+            aria-describedby=\"embed-1-footnote-label\">1</a>"
+        ));
+        assert!(result.contains("<li id=\"user-content-fn-embed-1-a\">"));
+        assert!(result.contains("<li id=\"user-content-fn-a\">"));
+        assert_eq!(result.matches("id=\"footnote-label\"").count(), 1);
+    }
+
+    #[rstest]
+    fn embeds_rebase_urls_relative_to_themselves(vault: TempFiles) {
+        let result = render("![[Illustrated]]", &options(&vault, "index.md"))
+            .expect("Failed to resolve wikilinks");
+
+        assert_eq!(
+            result,
+            "<div class=\"embed\">\n<p><img src=\"notes/pic.png\" alt=\"pic\" /> \
+            <a href=\"notes/Page%20Name.md\">page</a> \
+            <a href=\"Chapters.html\">Chapters</a></p>\n</div>"
+        );
+    }
+
+    #[rstest]
+    fn embeds_include_files_relative_to_themselves(vault: TempFiles) {
+        let options = MarkdownOptions {
+            includes: Some(Includes {
+                current: vault.root.join("index.md"),
+                root: vault.root.clone(),
+            }),
+            ..options(&vault, "index.md")
+        };
+        let result = render("![[Snippets]]", &options)
+            .expect("Failed to resolve wikilinks");
+
+        assert_eq!(result, "<div class=\"embed\">\n<p>Included.</p>\n</div>");
+    }
+
+    #[rstest]
+    fn embeds_of_other_files_become_images(vault: TempFiles) {
+        let result =
+            render("![[pic.png|A picture]]", &options(&vault, "index.md"))
+                .expect("Failed to resolve wikilinks");
+
+        assert_eq!(
+            result,
+            "<p><img src=\"images/pic.png\" alt=\"A picture\" /></p>"
+        );
+    }
+
+    #[rstest]
+    fn embedded_images_default_to_their_file_name_as_alt(vault: TempFiles) {
+        let result = render("![[pic.png]]", &options(&vault, "index.md"))
+            .expect("Failed to resolve wikilinks");
+
+        assert_eq!(result, "<p><img src=\"images/pic.png\" alt=\"pic\" /></p>");
+    }
+
+    #[rstest]
+    #[case::itself("![[Loop]]")]
+    #[case::itself_inside_a_paragraph("![[Spiral]]")]
+    #[case::each_other("![[Ping]]")]
+    #[case::the_current_page("See ![[index]].")]
+    fn embeds_error_on_cycles(vault: TempFiles, #[case] input: &str) {
+        let result = render(input, &options(&vault, "index.md"));
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn wikilinks_are_ignored_without_a_vault() {
+        let result = render("[[Page]]", &MarkdownOptions::default())
+            .expect("Failed to resolve wikilinks");
+
+        assert_eq!(result, "<p>[[Page]]</p>");
+    }
+}